@@ -1,6 +1,7 @@
-use crate::{Colorful, Screen};
+use crate::gfx::paint::Paint;
+use crate::Screen;
 
-fn plot_low(screen: &mut Screen, x0: i32, y0: i32, x1: i32, y1: i32, color: &impl Colorful) {
+fn plot_low(screen: &mut Screen, x0: i32, y0: i32, x1: i32, y1: i32, color: &impl Paint) {
     let dx = x1 - x0;
     let mut dy = y1 - y0;
     let mut yi = 1;
@@ -22,7 +23,7 @@ fn plot_low(screen: &mut Screen, x0: i32, y0: i32, x1: i32, y1: i32, color: &imp
     }
 }
 
-fn plot_high(screen: &mut Screen, x0: i32, y0: i32, x1: i32, y1: i32, color: &impl Colorful) {
+fn plot_high(screen: &mut Screen, x0: i32, y0: i32, x1: i32, y1: i32, color: &impl Paint) {
     let mut dx = x1 - x0;
     let dy = y1 - y0;
     let mut xi = 1;
@@ -44,7 +45,7 @@ fn plot_high(screen: &mut Screen, x0: i32, y0: i32, x1: i32, y1: i32, color: &im
     }
 }
 
-pub fn draw_line(screen: &mut Screen, x0: i32, y0: i32, x1: i32, y1: i32, color: &impl Colorful) {
+pub fn draw_line(screen: &mut Screen, x0: i32, y0: i32, x1: i32, y1: i32, color: &impl Paint) {
     if (y1 - y0).abs() < (x1 - x0).abs() {
         if x0 > x1 {
             plot_low(screen, x1, y1, x0, y0, color);
@@ -0,0 +1,270 @@
+use std::f32::consts::TAU;
+use std::sync::atomic::{AtomicU8, Ordering};
+use std::time::{Duration, Instant};
+use crate::gfx::color::model::Hsv;
+use crate::gfx::color::{Colorful, ColorfulCycle, RGBA};
+use crate::gfx::paint::ColorStops;
+
+/// Lets any infinite color iterator be turned into a `Colorful` with `.into_cycle()`.
+pub trait IntoCycle: Iterator<Item = RGBA> + Send + Sized + 'static {
+    fn into_cycle(self) -> ColorfulCycle {
+        ColorfulCycle::new(self)
+    }
+}
+
+impl<T: Iterator<Item = RGBA> + Send + 'static> IntoCycle for T {}
+
+/// Small xorshift random number generator. Not suitable for anything but picking colors, but keeps
+/// us from needing the `rand` crate.
+#[derive(Clone)]
+pub(crate) struct XorShift(u32);
+
+impl XorShift {
+    pub(crate) fn new(seed: u32) -> Self {
+        // Zero is the one state xorshift can't leave.
+        Self(if seed == 0 { 0x9E37_79B9 } else { seed })
+    }
+
+    pub(crate) fn next_u32(&mut self) -> u32 {
+        let mut x = self.0;
+        x ^= x << 13;
+        x ^= x >> 17;
+        x ^= x << 5;
+        self.0 = x;
+        x
+    }
+
+    /// Returns a float from 0.0 up to, but not including, 1.0.
+    pub(crate) fn next_f32(&mut self) -> f32 {
+        (self.next_u32() >> 8) as f32 / (1 << 24) as f32
+    }
+}
+
+/// The Rainbow gradually changes through every maximum brightness color that can be represented.
+/// It acts like color changing yarn, where you can't necessarily predict which pixels will be which
+/// color, but could work nicely when randomly distributed.
+#[derive(Default)]
+pub struct Rainbow {
+    hue: u8,
+}
+
+impl Rainbow {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl Iterator for Rainbow {
+    type Item = RGBA;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.hue = self.hue.wrapping_add(1);
+        Some(Hsv::new(self.hue as f32 * 360.0 / 256.0, 1.0, 1.0).as_rgba())
+    }
+}
+
+/// The same colors as `Rainbow`, but the hue is kept in an atomic instead of behind the `Mutex` of a
+/// `ColorfulCycle`. Threads never wait on each other, which matters for parallel fills.
+#[derive(Default)]
+pub struct AtomicRainbow {
+    hue: AtomicU8,
+}
+
+impl AtomicRainbow {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl Colorful for AtomicRainbow {
+    fn as_rgba(&self) -> RGBA {
+        let hue = self.hue.fetch_add(1, Ordering::Relaxed).wrapping_add(1);
+        Hsv::new(hue as f32 * 360.0 / 256.0, 1.0, 1.0).as_rgba()
+    }
+}
+
+/// Steps through a multi-stop gradient one color per call, then starts over.
+pub struct GradientCycle {
+    stops: ColorStops,
+    steps: usize,
+    step: usize,
+}
+
+impl GradientCycle {
+    /// Creates a cycle that takes `steps` calls to get through the whole gradient.
+    pub fn new(steps: usize) -> Self {
+        Self { stops: ColorStops::default(), steps: steps.max(1), step: 0 }
+    }
+
+    /// Adds a color stop at `offset`, between 0.0 and 1.0. Repeat the first color as the last stop
+    /// to avoid a jump when the cycle starts over.
+    pub fn with_stop(mut self, offset: f32, color: &impl Colorful) -> Self {
+        self.stops.push(offset, color);
+        self
+    }
+}
+
+impl Iterator for GradientCycle {
+    type Item = RGBA;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let color = self.stops.sample(self.step as f32 / self.steps as f32);
+        self.step = (self.step + 1) % self.steps;
+        Some(color)
+    }
+}
+
+/// Picks a random color from a palette on every call. The same seed always gives the same sequence,
+/// so a brush looks the same every time it is replayed.
+pub struct RandomPalette {
+    palette: Vec<RGBA>,
+    rng: XorShift,
+}
+
+impl RandomPalette {
+    pub fn new(seed: u32, palette: &[&dyn Colorful]) -> Self {
+        Self { palette: palette.iter().map(|c| c.as_rgba()).collect(), rng: XorShift::new(seed) }
+    }
+}
+
+impl Iterator for RandomPalette {
+    type Item = RGBA;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.palette.is_empty() {
+            return Some((0, 0, 0, 0));
+        }
+        let idx = self.rng.next_u32() as usize % self.palette.len();
+        Some(self.palette[idx])
+    }
+}
+
+/// A base color with the occasional bright fleck of glitter.
+pub struct Sparkle {
+    base: RGBA,
+    glitter: RGBA,
+    /// Chance from 0.0 to 1.0 that any one pixel sparkles.
+    density: f32,
+    rng: XorShift,
+}
+
+impl Sparkle {
+    /// Creates a sparkle where roughly `density` of all pixels are `glitter` instead of `base`. The
+    /// same `seed` always places the same flecks.
+    pub fn new(seed: u32, base: &impl Colorful, glitter: &impl Colorful, density: f32) -> Self {
+        Self { base: base.as_rgba(), glitter: glitter.as_rgba(), density, rng: XorShift::new(seed) }
+    }
+}
+
+impl Iterator for Sparkle {
+    type Item = RGBA;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.rng.next_f32() >= self.density {
+            return Some(self.base);
+        }
+        // Vary the brightness of each fleck so the glitter twinkles.
+        let shine = 0.6 + self.rng.next_f32() * 0.4;
+        let (r, g, b, a) = self.glitter;
+        let dim = |c: u8| (c as f32 * shine) as u8;
+        Some((dim(r), dim(g), dim(b), a))
+    }
+}
+
+/// Returns how far through the current period we are, from 0.0 to 1.0.
+fn phase(start: Instant, period: Duration) -> f32 {
+    let period = period.as_secs_f32().max(f32::EPSILON);
+    (start.elapsed().as_secs_f32() % period) / period
+}
+
+/// Moves through a gradient with the wall clock instead of per call, so every pixel drawn at the
+/// same moment gets the same color no matter how many pixels a primitive draws.
+pub struct TimedGradient {
+    stops: ColorStops,
+    period: Duration,
+    start: Instant,
+}
+
+impl TimedGradient {
+    /// Creates a gradient that takes `period` to loop.
+    pub fn new(period: Duration) -> Self {
+        Self { stops: ColorStops::default(), period, start: Instant::now() }
+    }
+
+    /// Adds a color stop at `offset`, between 0.0 and 1.0.
+    pub fn with_stop(mut self, offset: f32, color: &impl Colorful) -> Self {
+        self.stops.push(offset, color);
+        self
+    }
+}
+
+impl Colorful for TimedGradient {
+    fn as_rgba(&self) -> RGBA {
+        self.stops.sample(phase(self.start, self.period))
+    }
+}
+
+/// A rainbow that goes around the color wheel once every `period` of wall clock time.
+pub struct TimedRainbow {
+    period: Duration,
+    start: Instant,
+}
+
+impl TimedRainbow {
+    pub fn new(period: Duration) -> Self {
+        Self { period, start: Instant::now() }
+    }
+}
+
+impl Colorful for TimedRainbow {
+    fn as_rgba(&self) -> RGBA {
+        Hsv::new(phase(self.start, self.period) * 360.0, 1.0, 1.0).as_rgba()
+    }
+}
+
+/// A color that smoothly fades in and out, like a heartbeat or a "tap here" hint.
+pub struct Pulse {
+    color: RGBA,
+    period: Duration,
+    /// Lowest opacity reached, from 0.0 to 1.0.
+    min: f32,
+    start: Instant,
+}
+
+impl Pulse {
+    pub fn new(color: &impl Colorful, period: Duration) -> Self {
+        Self { color: color.as_rgba(), period, min: 0.0, start: Instant::now() }
+    }
+
+    /// Sets the lowest opacity reached, so the color never disappears completely.
+    pub fn with_min(mut self, min: f32) -> Self {
+        self.min = min.clamp(0.0, 1.0);
+        self
+    }
+}
+
+impl Colorful for Pulse {
+    fn as_rgba(&self) -> RGBA {
+        // Cosine wave from 1.0 down to `min` and back.
+        let wave = (1.0 + (phase(self.start, self.period) * TAU).cos()) / 2.0;
+        let strength = self.min + (1.0 - self.min) * wave;
+        let (r, g, b, a) = self.color;
+        (r, g, b, (a as f32 * strength) as u8)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sparkle_repeats_with_the_same_seed() {
+        let white = [255, 255, 255];
+        let black = [0, 0, 0];
+        let a: Vec<_> = Sparkle::new(7, &black, &white, 0.5).take(64).collect();
+        let b: Vec<_> = Sparkle::new(7, &black, &white, 0.5).take(64).collect();
+        let c: Vec<_> = Sparkle::new(8, &black, &white, 0.5).take(64).collect();
+        assert_eq!(a, b);
+        assert_ne!(a, c);
+    }
+}
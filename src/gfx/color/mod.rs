@@ -0,0 +1,103 @@
+use std::sync::Mutex;
+
+pub mod css;
+pub mod cycle;
+pub mod model;
+pub mod parse;
+
+/// Ergonomic rename of 4-tuple of bytes.
+#[allow(clippy::upper_case_acronyms)]
+pub type RGBA = (u8, u8, u8, u8);
+
+/// Trait which can be applied to anything that can represent a color. Colors must be shareable
+/// between threads, so any state that changes between calls needs to live behind an atomic or a
+/// `Mutex`.
+pub trait Colorful: Send + Sync {
+    /// Returns the struct as a 24-bit color with alpha channel.
+    fn as_rgba(&self) -> RGBA;
+}
+
+impl Colorful for [u8; 3] {
+    fn as_rgba(&self) -> RGBA {
+        (self[0], self[1], self[2], u8::MAX)
+    }
+}
+
+impl Colorful for [u8; 4] {
+    fn as_rgba(&self) -> RGBA {
+        (self[0], self[1], self[2], self[3])
+    }
+}
+
+/// A color stored by value. Use this when a color needs to be kept around, compared or read from
+/// text such as config files and themes, e.g. `"#ff8800".parse::<Color>()`.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub struct Color(pub RGBA);
+
+impl Colorful for Color {
+    fn as_rgba(&self) -> RGBA {
+        self.0
+    }
+}
+
+impl From<RGBA> for Color {
+    fn from(rgba: RGBA) -> Self {
+        Color(rgba)
+    }
+}
+
+/// A small palette of primary and kid-friendly colors. See `css` for the full set of CSS names.
+pub enum NamedColor {
+    Red,
+    Green,
+    Blue,
+    Black,
+    White,
+    Yellow,
+    Cyan,
+    Magenta,
+    Orange,
+    Purple,
+    Pink,
+    Brown,
+    Gray,
+}
+
+impl Colorful for NamedColor {
+    fn as_rgba(&self) -> RGBA {
+        match self {
+            NamedColor::Red   => (u8::MAX, 0, 0, u8::MAX),
+            NamedColor::Green => (0, u8::MAX, 0, u8::MAX),
+            NamedColor::Blue  => (0, 0, u8::MAX, u8::MAX),
+            NamedColor::Black => (0, 0, 0, u8::MAX),
+            NamedColor::White => (u8::MAX, u8::MAX, u8::MAX, u8::MAX),
+            NamedColor::Cyan => (0, u8::MAX, u8::MAX, u8::MAX),
+            NamedColor::Yellow => (u8::MAX, u8::MAX, 0, u8::MAX),
+            NamedColor::Magenta => (u8::MAX, 0, u8::MAX, u8::MAX),
+            NamedColor::Orange => (u8::MAX, 165, 0, u8::MAX),
+            NamedColor::Purple => (128, 0, 128, u8::MAX),
+            NamedColor::Pink => (u8::MAX, 192, 203, u8::MAX),
+            NamedColor::Brown => (165, 42, 42, u8::MAX),
+            NamedColor::Gray => (128, 128, 128, u8::MAX),
+        }
+    }
+}
+
+/// Turns an infinite iterator of colors into a `Colorful` that hands out the next color every time
+/// it is asked. See `cycle` for ready-made generators. The generator is behind a `Mutex` so one
+/// cycle can be shared with a render thread, with every thread taking turns pulling colors.
+pub struct ColorfulCycle {
+    pub generator: Mutex<Box<dyn Iterator<Item=RGBA> + Send>>
+}
+
+impl ColorfulCycle {
+    pub fn new(generator: impl Iterator<Item=RGBA> + Send + 'static) -> Self {
+        Self { generator: Mutex::new(Box::new(generator)) }
+    }
+}
+
+impl Colorful for ColorfulCycle {
+    fn as_rgba(&self) -> RGBA {
+        self.generator.lock().unwrap().next().unwrap()
+    }
+}
@@ -0,0 +1,309 @@
+use crate::gfx::color::{Colorful, RGBA};
+
+/// Colors that can be blended smoothly into each other. How the blend looks depends on the color
+/// space: RGB goes through muddy greys, HSV/HSL go around the color wheel, and Lab changes
+/// perceived lightness evenly.
+pub trait Interpolate {
+    /// Returns the color `t` of the way from `self` to `other`, where `t` is between 0.0 and 1.0.
+    fn interpolate(&self, other: &Self, t: f32) -> Self;
+}
+
+#[inline]
+fn lerp(a: f32, b: f32, t: f32) -> f32 {
+    a + (b - a) * t
+}
+
+/// Interpolates between two hues in degrees, going the short way around the color wheel.
+fn lerp_hue(a: f32, b: f32, t: f32) -> f32 {
+    let delta = (b - a + 180.0).rem_euclid(360.0) - 180.0;
+    (a + delta * t).rem_euclid(360.0)
+}
+
+#[inline]
+fn to_unit(c: u8) -> f32 {
+    c as f32 / u8::MAX as f32
+}
+
+#[inline]
+fn to_byte(c: f32) -> u8 {
+    (c.clamp(0.0, 1.0) * u8::MAX as f32).round() as u8
+}
+
+/// Returns the red, green and blue of a hue in degrees at full saturation and value.
+fn hue_to_rgb(h: f32) -> (f32, f32, f32) {
+    let h = h.rem_euclid(360.0) / 60.0;
+    let x = 1.0 - (h % 2.0 - 1.0).abs();
+    match h as u8 {
+        0 => (1.0, x, 0.0),
+        1 => (x, 1.0, 0.0),
+        2 => (0.0, 1.0, x),
+        3 => (0.0, x, 1.0),
+        4 => (x, 0.0, 1.0),
+        _ => (1.0, 0.0, x),
+    }
+}
+
+/// Returns the hue in degrees, the largest channel and the smallest channel of an RGB color.
+fn rgb_to_hue(r: f32, g: f32, b: f32) -> (f32, f32, f32) {
+    let max = r.max(g).max(b);
+    let min = r.min(g).min(b);
+    let chroma = max - min;
+
+    let hue = if chroma == 0.0 {
+        0.0
+    } else if max == r {
+        60.0 * ((g - b) / chroma).rem_euclid(6.0)
+    } else if max == g {
+        60.0 * ((b - r) / chroma + 2.0)
+    } else {
+        60.0 * ((r - g) / chroma + 4.0)
+    };
+
+    (hue, max, min)
+}
+
+impl Interpolate for RGBA {
+    fn interpolate(&self, other: &Self, t: f32) -> Self {
+        let mix = |a: u8, b: u8| lerp(a as f32, b as f32, t).round() as u8;
+        (mix(self.0, other.0), mix(self.1, other.1), mix(self.2, other.2), mix(self.3, other.3))
+    }
+}
+
+/// Hue, saturation and value. Good for color pickers: the hue is a wheel, saturation fades to white
+/// and value fades to black.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Hsv {
+    /// Hue in degrees, 0.0 to 360.0. 0 is red, 120 is green and 240 is blue.
+    pub h: f32,
+    /// Saturation, 0.0 to 1.0.
+    pub s: f32,
+    /// Value (brightness), 0.0 to 1.0.
+    pub v: f32,
+    /// Opacity, 0.0 to 1.0.
+    pub a: f32,
+}
+
+impl Hsv {
+    pub fn new(h: f32, s: f32, v: f32) -> Self {
+        Self { h, s, v, a: 1.0 }
+    }
+}
+
+impl Colorful for Hsv {
+    fn as_rgba(&self) -> RGBA {
+        let (r, g, b) = hue_to_rgb(self.h);
+        let s = self.s.clamp(0.0, 1.0);
+        let v = self.v.clamp(0.0, 1.0);
+        // Fade toward white by saturation, then toward black by value.
+        let channel = |c: f32| to_byte(v * (1.0 - s * (1.0 - c)));
+        (channel(r), channel(g), channel(b), to_byte(self.a))
+    }
+}
+
+impl From<RGBA> for Hsv {
+    fn from((r, g, b, a): RGBA) -> Self {
+        let (h, max, min) = rgb_to_hue(to_unit(r), to_unit(g), to_unit(b));
+        let s = if max == 0.0 { 0.0 } else { (max - min) / max };
+        Self { h, s, v: max, a: to_unit(a) }
+    }
+}
+
+impl Interpolate for Hsv {
+    fn interpolate(&self, other: &Self, t: f32) -> Self {
+        Self {
+            h: lerp_hue(self.h, other.h, t),
+            s: lerp(self.s, other.s, t),
+            v: lerp(self.v, other.v, t),
+            a: lerp(self.a, other.a, t),
+        }
+    }
+}
+
+/// Hue, saturation and lightness, as used by CSS `hsl()`. Lightness 0.5 is the pure color, with
+/// black below and white above.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Hsl {
+    /// Hue in degrees, 0.0 to 360.0.
+    pub h: f32,
+    /// Saturation, 0.0 to 1.0.
+    pub s: f32,
+    /// Lightness, 0.0 to 1.0.
+    pub l: f32,
+    /// Opacity, 0.0 to 1.0.
+    pub a: f32,
+}
+
+impl Hsl {
+    pub fn new(h: f32, s: f32, l: f32) -> Self {
+        Self { h, s, l, a: 1.0 }
+    }
+}
+
+impl Colorful for Hsl {
+    fn as_rgba(&self) -> RGBA {
+        let (r, g, b) = hue_to_rgb(self.h);
+        let s = self.s.clamp(0.0, 1.0);
+        let l = self.l.clamp(0.0, 1.0);
+        let chroma = (1.0 - (2.0 * l - 1.0).abs()) * s;
+        let min = l - chroma / 2.0;
+        let channel = |c: f32| to_byte(min + c * chroma);
+        (channel(r), channel(g), channel(b), to_byte(self.a))
+    }
+}
+
+impl From<RGBA> for Hsl {
+    fn from((r, g, b, a): RGBA) -> Self {
+        let (h, max, min) = rgb_to_hue(to_unit(r), to_unit(g), to_unit(b));
+        let l = (max + min) / 2.0;
+        let s = if max == min { 0.0 } else { (max - min) / (1.0 - (2.0 * l - 1.0).abs()) };
+        Self { h, s, l, a: to_unit(a) }
+    }
+}
+
+impl Interpolate for Hsl {
+    fn interpolate(&self, other: &Self, t: f32) -> Self {
+        Self {
+            h: lerp_hue(self.h, other.h, t),
+            s: lerp(self.s, other.s, t),
+            l: lerp(self.l, other.l, t),
+            a: lerp(self.a, other.a, t),
+        }
+    }
+}
+
+/// CIELAB, a color space designed so equal distances look like equal differences. Use it for
+/// gradients that shouldn't have a bright or dark band in the middle, and for comparing colors.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Lab {
+    /// Perceived lightness, 0.0 (black) to 100.0 (white).
+    pub l: f32,
+    /// Green (negative) to red (positive), roughly -128.0 to 127.0.
+    pub a: f32,
+    /// Blue (negative) to yellow (positive), roughly -128.0 to 127.0.
+    pub b: f32,
+    /// Opacity, 0.0 to 1.0.
+    pub alpha: f32,
+}
+
+/// Reference white for the D65 illuminant, which is what sRGB is defined against.
+const WHITE: (f32, f32, f32) = (0.950_47, 1.0, 1.088_83);
+
+impl Lab {
+    pub fn new(l: f32, a: f32, b: f32) -> Self {
+        Self { l, a, b, alpha: 1.0 }
+    }
+
+    /// Returns how different two colors look (CIE76 delta E). Around 2.3 is just noticeable.
+    pub fn distance(&self, other: &Self) -> f32 {
+        let (dl, da, db) = (self.l - other.l, self.a - other.a, self.b - other.b);
+        (dl * dl + da * da + db * db).sqrt()
+    }
+}
+
+/// Undoes the sRGB transfer curve.
+fn srgb_to_linear(c: f32) -> f32 {
+    if c <= 0.040_45 { c / 12.92 } else { ((c + 0.055) / 1.055).powf(2.4) }
+}
+
+/// Applies the sRGB transfer curve.
+fn linear_to_srgb(c: f32) -> f32 {
+    if c <= 0.003_130_8 { c * 12.92 } else { 1.055 * c.powf(1.0 / 2.4) - 0.055 }
+}
+
+impl Colorful for Lab {
+    fn as_rgba(&self) -> RGBA {
+        const DELTA: f32 = 6.0 / 29.0;
+        let finv = |t: f32| if t > DELTA { t * t * t } else { 3.0 * DELTA * DELTA * (t - 4.0 / 29.0) };
+
+        let fy = (self.l + 16.0) / 116.0;
+        let x = WHITE.0 * finv(fy + self.a / 500.0);
+        let y = WHITE.1 * finv(fy);
+        let z = WHITE.2 * finv(fy - self.b / 200.0);
+
+        let r = 3.240_454_2 * x - 1.537_138_5 * y - 0.498_531_4 * z;
+        let g = -0.969_266 * x + 1.876_010_8 * y + 0.041_556 * z;
+        let b = 0.055_643_4 * x - 0.204_025_9 * y + 1.057_225_2 * z;
+
+        let channel = |c: f32| to_byte(linear_to_srgb(c));
+        (channel(r), channel(g), channel(b), to_byte(self.alpha))
+    }
+}
+
+impl From<RGBA> for Lab {
+    fn from((r, g, b, a): RGBA) -> Self {
+        const DELTA: f32 = 6.0 / 29.0;
+        let f = |t: f32| if t > DELTA * DELTA * DELTA { t.cbrt() } else { t / (3.0 * DELTA * DELTA) + 4.0 / 29.0 };
+
+        let (r, g, b) = (srgb_to_linear(to_unit(r)), srgb_to_linear(to_unit(g)), srgb_to_linear(to_unit(b)));
+        let x = 0.412_456_4 * r + 0.357_576_1 * g + 0.180_437_5 * b;
+        let y = 0.212_672_9 * r + 0.715_152_2 * g + 0.072_175 * b;
+        let z = 0.019_333_9 * r + 0.119_192 * g + 0.950_304_1 * b;
+
+        let (fx, fy, fz) = (f(x / WHITE.0), f(y / WHITE.1), f(z / WHITE.2));
+        Self { l: 116.0 * fy - 16.0, a: 500.0 * (fx - fy), b: 200.0 * (fy - fz), alpha: to_unit(a) }
+    }
+}
+
+impl Interpolate for Lab {
+    fn interpolate(&self, other: &Self, t: f32) -> Self {
+        Self {
+            l: lerp(self.l, other.l, t),
+            a: lerp(self.a, other.a, t),
+            b: lerp(self.b, other.b, t),
+            alpha: lerp(self.alpha, other.alpha, t),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A spread of colors, including greys and the corners of the RGB cube.
+    const COLORS: [RGBA; 10] = [
+        (0, 0, 0, 255), (255, 255, 255, 255), (128, 128, 128, 128), (255, 0, 0, 255), (0, 255, 0, 255),
+        (0, 0, 255, 0), (255, 165, 0, 255), (75, 0, 130, 255), (12, 200, 99, 64), (250, 128, 114, 255),
+    ];
+
+    #[test]
+    fn hsv_round_trips() {
+        for color in COLORS {
+            assert_eq!(Hsv::from(color).as_rgba(), color);
+        }
+        assert_eq!(Hsv::new(120.0, 1.0, 1.0).as_rgba(), (0, 255, 0, 255));
+    }
+
+    #[test]
+    fn hsl_round_trips() {
+        for color in COLORS {
+            assert_eq!(Hsl::from(color).as_rgba(), color);
+        }
+        assert_eq!(Hsl::new(240.0, 1.0, 0.5).as_rgba(), (0, 0, 255, 255));
+    }
+
+    #[test]
+    fn lab_round_trips() {
+        for color in COLORS {
+            assert_eq!(Lab::from(color).as_rgba(), color);
+        }
+        let white = Lab::from((255, 255, 255, 255));
+        assert!((white.l - 100.0).abs() < 0.01 && white.a.abs() < 0.01 && white.b.abs() < 0.01);
+        assert!(Lab::from((0, 0, 0, 255)).distance(&white) > 99.0);
+    }
+
+    #[test]
+    fn out_of_range_values_are_clamped() {
+        assert_eq!(Hsv::new(-240.0, 2.0, 1.5).as_rgba(), Hsv::new(120.0, 1.0, 1.0).as_rgba());
+        assert_eq!(Hsl::new(600.0, -1.0, 0.5).as_rgba(), (128, 128, 128, 255));
+        assert_eq!(Lab::new(150.0, 0.0, 0.0).as_rgba(), (255, 255, 255, 255));
+    }
+
+    #[test]
+    fn hues_interpolate_the_short_way() {
+        let red = Hsv::new(350.0, 1.0, 1.0);
+        let also_red = Hsv::new(10.0, 1.0, 1.0);
+        assert_eq!(red.interpolate(&also_red, 0.5).h, 0.0);
+        assert_eq!(also_red.interpolate(&red, 0.25).h, 5.0);
+        assert_eq!((0, 0, 0, 0).interpolate(&(255, 255, 255, 255), 0.5), (128, 128, 128, 128));
+    }
+}
@@ -0,0 +1,171 @@
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+use crate::gfx::color::model::Hsl;
+use crate::gfx::color::{css, Color, Colorful, RGBA};
+
+/// Returned when text can't be understood as a color.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseColorError {
+    input: String,
+}
+
+impl Display for ParseColorError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "invalid color: {:?}", self.input)
+    }
+}
+
+impl Error for ParseColorError {}
+
+/// Parses the color formats CSS understands: `#RGB`, `#RGBA`, `#RRGGBB`, `#RRGGBBAA`,
+/// `rgb()`/`rgba()`, `hsl()`/`hsla()`, named colors and `transparent`. Function arguments can be
+/// separated by commas or spaces, with an optional `/ alpha` at the end.
+impl FromStr for Color {
+    type Err = ParseColorError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let err = || ParseColorError { input: s.to_owned() };
+        let text = s.trim().to_ascii_lowercase();
+
+        if let Some(hex) = text.strip_prefix('#') {
+            return parse_hex(hex).map(Color).ok_or_else(err);
+        }
+
+        if let Some((name, args)) = text.strip_suffix(')').and_then(|t| t.split_once('(')) {
+            let args = split_args(args);
+            return match name.trim() {
+                "rgb" | "rgba" => parse_rgb(&args),
+                "hsl" | "hsla" => parse_hsl(&args),
+                _ => None,
+            }.ok_or_else(err);
+        }
+
+        if text == "transparent" {
+            return Ok(Color((0, 0, 0, 0)));
+        }
+
+        css::by_name(&text).map(Color).ok_or_else(err)
+    }
+}
+
+/// Parses the digits of a hex color, expanding the short forms by repeating each digit.
+fn parse_hex(hex: &str) -> Option<RGBA> {
+    if !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+
+    let digit = |i: usize| u8::from_str_radix(&hex[i..i + 1], 16).ok().map(|d| d * 17);
+    let pair = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).ok();
+
+    match hex.len() {
+        3 => Some((digit(0)?, digit(1)?, digit(2)?, u8::MAX)),
+        4 => Some((digit(0)?, digit(1)?, digit(2)?, digit(3)?)),
+        6 => Some((pair(0)?, pair(2)?, pair(4)?, u8::MAX)),
+        8 => Some((pair(0)?, pair(2)?, pair(4)?, pair(6)?)),
+        _ => None,
+    }
+}
+
+/// Splits function arguments on commas, whitespace and the `/` before alpha.
+fn split_args(args: &str) -> Vec<&str> {
+    args.split(|c: char| c == ',' || c == '/' || c.is_whitespace())
+        .filter(|a| !a.is_empty())
+        .collect()
+}
+
+/// Parses a number, or a percentage of `scale`.
+fn parse_number(arg: &str, scale: f32) -> Option<f32> {
+    match arg.strip_suffix('%') {
+        Some(pct) => pct.parse::<f32>().ok().map(|v| v / 100.0 * scale),
+        None => arg.parse().ok(),
+    }
+}
+
+/// Parses an alpha value, which is either 0.0 to 1.0 or a percentage. Missing alpha is opaque.
+fn parse_alpha(arg: Option<&&str>) -> Option<f32> {
+    match arg {
+        Some(arg) => parse_number(arg, 1.0).map(|a| a.clamp(0.0, 1.0)),
+        None => Some(1.0),
+    }
+}
+
+fn parse_rgb(args: &[&str]) -> Option<Color> {
+    if args.len() != 3 && args.len() != 4 {
+        return None;
+    }
+    let channel = |arg: &str| parse_number(arg, 255.0).map(|v| v.round().clamp(0.0, 255.0) as u8);
+    let alpha = parse_alpha(args.get(3))?;
+
+    Some(Color((channel(args[0])?, channel(args[1])?, channel(args[2])?, (alpha * 255.0).round() as u8)))
+}
+
+fn parse_hsl(args: &[&str]) -> Option<Color> {
+    if args.len() != 3 && args.len() != 4 {
+        return None;
+    }
+    let h = args[0].strip_suffix("deg").unwrap_or(args[0]).parse::<f32>().ok()?;
+    // Saturation and lightness are percentages, but be lenient about a missing `%`.
+    let percent = |arg: &str| arg.strip_suffix('%').unwrap_or(arg).parse::<f32>().ok().map(|v| v / 100.0);
+    let (s, l) = (percent(args[1])?, percent(args[2])?);
+    let a = parse_alpha(args.get(3))?;
+
+    Some(Color(Hsl { h, s, l, a }.as_rgba()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(text: &str) -> Option<RGBA> {
+        text.parse::<Color>().ok().map(|color| color.0)
+    }
+
+    #[test]
+    fn parses_hex() {
+        assert_eq!(parse("#f80"), Some((255, 136, 0, 255)));
+        assert_eq!(parse("#F808"), Some((255, 136, 0, 136)));
+        assert_eq!(parse("#ff8800"), Some((255, 136, 0, 255)));
+        assert_eq!(parse("  #FF880080 "), Some((255, 136, 0, 128)));
+        assert_eq!(parse("#ff88001"), None);
+        assert_eq!(parse("#"), None);
+        assert_eq!(parse("#ggg"), None);
+        assert_eq!(parse("#+f0"), None);
+        assert_eq!(parse("#ffé"), None);
+    }
+
+    #[test]
+    fn parses_functions() {
+        assert_eq!(parse("rgb(255, 136, 0)"), Some((255, 136, 0, 255)));
+        assert_eq!(parse("rgba(100% 0% 50% / 50%)"), Some((255, 0, 128, 128)));
+        assert_eq!(parse("RGB(1 2 3 / 0.5)"), Some((1, 2, 3, 128)));
+        assert_eq!(parse("hsl(120deg, 100%, 50%)"), Some((0, 255, 0, 255)));
+        assert_eq!(parse("hsla(240 100 50 / 0.25)"), Some((0, 0, 255, 64)));
+        assert_eq!(parse("rgb(1, 2)"), None);
+        assert_eq!(parse("rgb(1, 2, 3, 4, 5)"), None);
+        assert_eq!(parse("rgb(a, b, c)"), None);
+        assert_eq!(parse("cmyk(0, 0, 0, 0)"), None);
+    }
+
+    #[test]
+    fn clamps_out_of_range_arguments() {
+        assert_eq!(parse("rgb(300, -5, 128.4)"), Some((255, 0, 128, 255)));
+        assert_eq!(parse("rgba(0, 0, 0, 2)"), Some((0, 0, 0, 255)));
+        // Hues wrap around, and saturation and lightness are clamped.
+        assert_eq!(parse("hsl(480, 100%, 50%)"), Some((0, 255, 0, 255)));
+        assert_eq!(parse("hsl(-120, 100%, 50%)"), Some((0, 0, 255, 255)));
+        assert_eq!(parse("hsl(0, 150%, 120%)"), Some((255, 255, 255, 255)));
+        assert_eq!(parse("hsl(0, 100%, -10%, -1)"), Some((0, 0, 0, 0)));
+    }
+
+    #[test]
+    fn parses_names() {
+        assert_eq!(parse("RebeccaPurple"), Some((102, 51, 153, 255)));
+        assert_eq!(parse("transparent"), Some((0, 0, 0, 0)));
+        assert_eq!(parse("notacolor"), None);
+        assert_eq!("nope".parse::<Color>().unwrap_err().to_string(), "invalid color: \"nope\"");
+        // The table has to stay sorted for lookups to work.
+        assert!(css::CSS_COLORS.windows(2).all(|pair| pair[0].0 < pair[1].0));
+        assert!(css::CSS_COLORS.iter().all(|&(name, color)| css::by_name(name) == Some(color)));
+    }
+}
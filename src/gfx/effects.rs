@@ -0,0 +1,288 @@
+use crate::gfx::color::{Colorful, RGBA};
+
+/// Which blur to run. Both are separable running-sum box blurs so their cost does not depend on
+/// the radius, which keeps them usable on a Pi 3A+.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Blur {
+    /// A single box blur pass. Cheapest, but leaves visible square artifacts on hard edges.
+    Box(usize),
+    /// Three box blur passes, which is a close approximation of a gaussian blur with the given
+    /// radius.
+    Gaussian(usize),
+}
+
+impl Blur {
+    /// Returns the radius of each box pass and the number of passes.
+    fn passes(&self) -> (usize, usize) {
+        match *self {
+            Blur::Box(radius) => (radius, 1),
+            // Three passes of radius r have a spread of roughly 1.7r, so split the radius up.
+            Blur::Gaussian(radius) => (radius.div_ceil(2), 3),
+        }
+    }
+
+    /// Returns how far the blur can spread a pixel in any direction.
+    pub fn extent(&self) -> usize {
+        let (radius, passes) = self.passes();
+        radius * passes
+    }
+}
+
+/// Blurs a buffer of `w` by `h` pixels with `channels` bytes per pixel, in place. Every channel is
+/// blurred independently, so this works for alpha masks (1), RGB (3) and RGBA (4) buffers.
+pub fn blur(data: &mut [u8], w: usize, h: usize, channels: usize, blur: Blur) {
+    let (radius, passes) = blur.passes();
+    if radius == 0 || w == 0 || h == 0 {
+        return;
+    }
+
+    let mut line = Vec::with_capacity(w.max(h));
+    for _ in 0..passes {
+        for c in 0..channels {
+            for y in 0..h {
+                blur_line(data, y * w * channels + c, channels, w, radius, &mut line);
+            }
+            for x in 0..w {
+                blur_line(data, x * channels + c, w * channels, h, radius, &mut line);
+            }
+        }
+    }
+}
+
+/// Runs a box blur over `len` values starting at `start` and `stride` bytes apart. Pixels past the
+/// edges repeat the edge pixel, so blurring a region of the screen doesn't darken its border.
+fn blur_line(data: &mut [u8], start: usize, stride: usize, len: usize, radius: usize, line: &mut Vec<u8>) {
+    line.clear();
+    line.extend((0..len).map(|i| data[start + i * stride]));
+
+    let at = |i: isize| line[i.clamp(0, len as isize - 1) as usize] as u32;
+    let radius = radius as isize;
+    let window = (radius * 2 + 1) as u32;
+    let mut sum: u32 = (-radius..=radius).map(at).sum();
+
+    for i in 0..len as isize {
+        data[start + i as usize * stride] = ((sum + window / 2) / window) as u8;
+
+        // Slide the window one pixel to the right.
+        sum += at(i + radius + 1);
+        sum -= at(i - radius);
+    }
+}
+
+/// Grows the opaque parts of an alpha mask by `spread` pixels in every direction, in place.
+pub fn dilate(mask: &mut [u8], w: usize, h: usize, spread: usize) {
+    if spread == 0 {
+        return;
+    }
+
+    let mut line = Vec::with_capacity(w.max(h));
+    let mut max_line = |start: usize, stride: usize, len: usize| {
+        line.clear();
+        line.extend((0..len).map(|i| mask[start + i * stride]));
+        for i in 0..len {
+            let lo = i.saturating_sub(spread);
+            let hi = (i + spread).min(len - 1);
+            mask[start + i * stride] = line[lo..=hi].iter().copied().max().unwrap_or(0);
+        }
+    };
+
+    for y in 0..h {
+        max_line(y * w, 1, w);
+    }
+    for x in 0..w {
+        max_line(x, w, h);
+    }
+}
+
+/// Grows the opaque parts of an alpha mask by `radius` pixels with round corners, such as for
+/// outlining text. The edge is anti-aliased. Slower than `dilate`, so best kept to small radii.
+pub fn dilate_round(mask: &[u8], w: usize, h: usize, radius: usize) -> Vec<u8> {
+    let r = radius as i32;
+    // Every offset within the disc, and how much of its pixel the disc covers.
+    let disc: Vec<(i32, i32, f32)> = (-r..=r)
+        .flat_map(|dy| (-r..=r).map(move |dx| (dx, dy)))
+        .map(|(dx, dy)| (dx, dy, (radius as f32 + 0.5 - ((dx * dx + dy * dy) as f32).sqrt()).clamp(0.0, 1.0)))
+        .filter(|&(_, _, weight)| weight > 0.0)
+        .collect();
+
+    let mut out = vec![0u8; w * h];
+    for y in 0..h as i32 {
+        for x in 0..w as i32 {
+            let value = disc.iter()
+                .filter(|&&(dx, dy, _)| x + dx >= 0 && x + dx < w as i32 && y + dy >= 0 && y + dy < h as i32)
+                .map(|&(dx, dy, weight)| mask[((y + dy) * w as i32 + x + dx) as usize] as f32 * weight)
+                .fold(0.0, f32::max);
+            out[(y * w as i32 + x) as usize] = value as u8;
+        }
+    }
+    out
+}
+
+/// Builds an alpha mask for a rectangle with rounded corners. Unlike `Screen::draw_rect` the
+/// corners are anti-aliased, since the mask is usually blurred afterwards anyway.
+pub fn rect_mask(w: usize, h: usize, radius: usize) -> Vec<u8> {
+    let radius = radius.min(w / 2).min(h / 2) as f32;
+    let mut mask = vec![0u8; w * h];
+
+    for y in 0..h {
+        for x in 0..w {
+            // Distance from the pixel center to the nearest point of the rectangle shrunk by the
+            // radius. Anything within the radius of that rectangle is inside.
+            let px = x as f32 + 0.5;
+            let py = y as f32 + 0.5;
+            let dx = (radius - px).max(px - (w as f32 - radius)).max(0.0);
+            let dy = (radius - py).max(py - (h as f32 - radius)).max(0.0);
+            let coverage = if dx == 0.0 && dy == 0.0 {
+                // Inside the shrunk rectangle, which is all of it when there is no radius.
+                1.0
+            } else {
+                (radius - (dx * dx + dy * dy).sqrt() + 0.5).clamp(0.0, 1.0)
+            };
+            mask[y * w + x] = (coverage * u8::MAX as f32) as u8;
+        }
+    }
+
+    mask
+}
+
+/// Pulls the alpha channel out of an RGBA buffer, such as the ones produced by `decode_image` or
+/// `TextRenderer::render`.
+pub fn alpha_mask(rgba: &[u8]) -> Vec<u8> {
+    rgba.chunks(4).map(|px| px[3]).collect()
+}
+
+/// A soft shadow cast by a shape. Drop shadows are offset and blurred, while glows sit directly
+/// behind the shape and are grown outward with `spread` before blurring.
+#[derive(Clone, Copy, Debug)]
+pub struct Shadow {
+    /// How far the shadow is moved from the shape, in pixels.
+    pub offset: (i32, i32),
+    /// How soft the edge of the shadow is.
+    pub blur: Blur,
+    /// How many pixels the shape is grown by before blurring.
+    pub spread: usize,
+    /// The shadow color. Its alpha is the opacity of the fully covered parts of the shadow.
+    pub color: RGBA,
+}
+
+impl Shadow {
+    /// A shadow offset below and to the right of the shape, as if lit from the top left.
+    pub fn drop(dx: i32, dy: i32, radius: usize, color: &impl Colorful) -> Self {
+        Self { offset: (dx, dy), blur: Blur::Gaussian(radius), spread: 0, color: color.as_rgba() }
+    }
+
+    /// A glow that surrounds the shape evenly.
+    pub fn glow(radius: usize, color: &impl Colorful) -> Self {
+        Self { offset: (0, 0), blur: Blur::Gaussian(radius), spread: radius / 2, color: color.as_rgba() }
+    }
+
+    /// Returns how many pixels the shadow can extend past the shape on each side, ignoring offset.
+    pub fn margin(&self) -> usize {
+        self.spread + self.blur.extent()
+    }
+
+    /// Renders the shadow of a `w` by `h` alpha mask. The result is `margin()` pixels larger on
+    /// every side than the input so the blur never reaches its edges. Draw it at the shape position
+    /// minus the margin, plus the offset.
+    pub fn render_mask(&self, mask: &[u8], w: usize, h: usize) -> (Vec<u8>, usize, usize) {
+        let margin = self.margin();
+        let (sw, sh) = (w + margin * 2, h + margin * 2);
+
+        let mut shadow = vec![0u8; sw * sh];
+        for y in 0..h {
+            let dst = (y + margin) * sw + margin;
+            shadow[dst..dst + w].copy_from_slice(&mask[y * w..(y + 1) * w]);
+        }
+
+        dilate(&mut shadow, sw, sh, self.spread);
+        blur(&mut shadow, sw, sh, 1, self.blur);
+
+        (shadow, sw, sh)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A `size` by `size` mask with an opaque `block` by `block` square in the middle.
+    fn square(size: usize, block: usize) -> Vec<u8> {
+        let start = (size - block) / 2;
+        let inside = |i: usize| (start..start + block).contains(&i);
+        (0..size * size).map(|i| if inside(i % size) && inside(i / size) { 255 } else { 0 }).collect()
+    }
+
+    fn total(data: &[u8]) -> u32 {
+        data.iter().map(|&v| v as u32).sum()
+    }
+
+    #[test]
+    fn blur_keeps_a_constant_field_constant() {
+        for blur_kind in [Blur::Box(3), Blur::Gaussian(5)] {
+            let mut data = vec![120u8; 16 * 10 * 3];
+            blur(&mut data, 16, 10, 3, blur_kind);
+            assert!(data.iter().all(|&v| v == 120), "{blur_kind:?}");
+        }
+    }
+
+    #[test]
+    fn gaussian_blur_conserves_mass_away_from_the_edges() {
+        let mut data = square(41, 9);
+        let before = total(&data);
+        blur(&mut data, 41, 41, 1, Blur::Gaussian(4));
+
+        // Each pass rounds, so allow a little drift.
+        let after = total(&data);
+        assert!(before.abs_diff(after) * 100 < before, "{before} became {after}");
+        // The square spreads out, so its middle gets lighter and its surroundings darker.
+        assert!(data[20 * 41 + 20] < 255);
+        assert!(data[20 * 41 + 20 - 6] > 0);
+        assert_eq!(data[0], 0);
+    }
+
+    #[test]
+    fn blur_leaves_channels_apart() {
+        let mut data: Vec<u8> = (0..8 * 8).flat_map(|_| [10, 200]).collect();
+        blur(&mut data, 8, 8, 2, Blur::Box(2));
+        assert!(data.chunks(2).all(|px| px == [10, 200]));
+    }
+
+    #[test]
+    fn dilate_grows_by_spread() {
+        let mut mask = square(11, 1);
+        dilate(&mut mask, 11, 11, 2);
+        // A single pixel becomes a square two pixels larger on every side.
+        assert_eq!(mask, square(11, 5));
+    }
+
+    #[test]
+    fn rect_mask_fills_inside_and_rounds_corners() {
+        let mask = rect_mask(20, 10, 4);
+        assert_eq!(mask[5 * 20 + 10], 255);
+        assert_eq!(mask[5 * 20], 255);
+        assert_eq!(mask[10], 255);
+        assert_eq!(mask[0], 0);
+        assert_eq!(mask[9 * 20 + 19], 0);
+
+        // Without a radius every pixel is covered.
+        assert!(rect_mask(6, 4, 0).iter().all(|&v| v == 255));
+    }
+
+    #[test]
+    fn shadow_mask_grows_by_margin_per_side() {
+        let shadow = Shadow { offset: (3, 4), blur: Blur::Gaussian(4), spread: 2, color: (0, 0, 0, 128) };
+        let margin = shadow.margin();
+        assert_eq!(margin, 2 + Blur::Gaussian(4).extent());
+
+        let mask = rect_mask(30, 20, 0);
+        let (out, w, h) = shadow.render_mask(&mask, 30, 20);
+        assert_eq!((w, h), (30 + margin * 2, 20 + margin * 2));
+        assert_eq!(out.len(), w * h);
+
+        // The shape is still solid in the middle, and only the faintest tail of the blur reaches
+        // the border, so none of it was cut off.
+        assert_eq!(out[(h / 2) * w + w / 2], 255);
+        let border = (0..w).flat_map(|x| [x, (h - 1) * w + x]).chain((0..h).flat_map(|y| [y * w, y * w + w - 1]));
+        assert!(border.map(|i| out[i]).all(|v| v <= 2));
+    }
+}
@@ -0,0 +1,157 @@
+use crate::gfx::paint::Paint;
+use crate::{Screen, SCREEN_H, SCREEN_W};
+
+/// Which neighbours of a pixel count as connected to it.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Connectivity {
+    /// Only the pixels directly above, below, left and right. Thin diagonal lines act as walls,
+    /// which is usually what you want for a coloring book.
+    Four,
+    /// Diagonal pixels are connected too, so fills leak through diagonal gaps.
+    Eight,
+}
+
+/// Scanline flood fill. Rather than visiting every pixel through a stack, it fills whole horizontal
+/// runs at a time and only pushes one seed per run found on the rows above and below.
+pub fn flood_fill(screen: &mut Screen, x: usize, y: usize, tolerance: u8, connectivity: Connectivity, color: &impl Paint) {
+    if x >= SCREEN_W || y >= SCREEN_H {
+        return;
+    }
+
+    let target = screen.raw_px(x, y);
+    let target_rgb = screen.get_px(x, y);
+
+    // Compare in the native pixel format when we can. Otherwise, compare each channel.
+    let matches = |screen: &Screen, x: usize, y: usize| -> bool {
+        if tolerance == 0 {
+            return screen.raw_px(x, y) == target;
+        }
+        let (r, g, b) = screen.get_px(x, y);
+        r.abs_diff(target_rgb.0) <= tolerance
+            && g.abs_diff(target_rgb.1) <= tolerance
+            && b.abs_diff(target_rgb.2) <= tolerance
+    };
+
+    // The paint may produce colors that still match the target, so keep track of what has been
+    // filled instead of relying on the pixel changing.
+    let mut filled = vec![false; SCREEN_W * SCREEN_H];
+    let mut seeds = vec![(x, y)];
+
+    while let Some((x, y)) = seeds.pop() {
+        if filled[y * SCREEN_W + x] || !matches(screen, x, y) {
+            continue;
+        }
+
+        // Walk out to both ends of the run.
+        let mut left = x;
+        while left > 0 && !filled[y * SCREEN_W + left - 1] && matches(screen, left - 1, y) {
+            left -= 1;
+        }
+        let mut right = x;
+        while right + 1 < SCREEN_W && !filled[y * SCREEN_W + right + 1] && matches(screen, right + 1, y) {
+            right += 1;
+        }
+
+        for px in left..=right {
+            filled[y * SCREEN_W + px] = true;
+            screen.blend_px(px, y, color);
+        }
+
+        // With 8-connectivity, the runs above and below may start one pixel diagonally outside.
+        let (scan_left, scan_right) = match connectivity {
+            Connectivity::Four => (left, right),
+            Connectivity::Eight => (left.saturating_sub(1), (right + 1).min(SCREEN_W - 1)),
+        };
+
+        let rows = [y.checked_sub(1), Some(y + 1).filter(|&ny| ny < SCREEN_H)];
+        for ny in rows.into_iter().flatten() {
+            // Push one seed at the start of every matching run in the row.
+            let mut in_run = false;
+            for px in scan_left..=scan_right {
+                let open = !filled[ny * SCREEN_W + px] && matches(screen, px, ny);
+                if open && !in_run {
+                    seeds.push((px, ny));
+                }
+                in_run = open;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gfx::color::NamedColor;
+
+    fn count(screen: &Screen, color: (u8, u8, u8)) -> usize {
+        (0..SCREEN_H).flat_map(|y| (0..SCREEN_W).map(move |x| (x, y))).filter(|&(x, y)| screen.get_px(x, y) == color).count()
+    }
+
+    /// A diagonal line from the top left corner, which leaves `x < y` below it. Lines stop short
+    /// of their end point.
+    fn diagonal() -> Screen {
+        let mut screen = Screen::in_memory();
+        screen.draw_line(0, 0, SCREEN_H, SCREEN_H, &NamedColor::White);
+        screen
+    }
+
+    #[test]
+    fn fills_inside_a_box() {
+        let mut screen = Screen::in_memory();
+        screen.draw_line(10, 10, 20, 10, &NamedColor::White);
+        screen.draw_line(20, 10, 20, 20, &NamedColor::White);
+        screen.draw_line(20, 20, 10, 20, &NamedColor::White);
+        screen.draw_line(10, 20, 10, 10, &NamedColor::White);
+
+        flood_fill(&mut screen, 15, 15, 0, Connectivity::Four, &NamedColor::Red);
+        assert_eq!(count(&screen, (248, 0, 0)), 9 * 9);
+        assert_eq!(screen.get_px(11, 11), (248, 0, 0));
+        assert_eq!(screen.get_px(10, 11), (248, 252, 248));
+        assert_eq!(screen.get_px(9, 11), (0, 0, 0));
+    }
+
+    #[test]
+    fn diagonals_are_walls_with_four_connectivity() {
+        let mut screen = diagonal();
+        flood_fill(&mut screen, 0, 10, 0, Connectivity::Four, &NamedColor::Red);
+        assert_eq!(count(&screen, (248, 0, 0)), SCREEN_H * (SCREEN_H - 1) / 2);
+
+        let mut screen = diagonal();
+        flood_fill(&mut screen, 0, 10, 0, Connectivity::Eight, &NamedColor::Red);
+        assert_eq!(count(&screen, (248, 0, 0)), SCREEN_W * SCREEN_H - SCREEN_H);
+    }
+
+    #[test]
+    fn tolerance_takes_in_similar_colors() {
+        let mut screen = Screen::in_memory();
+        screen.draw_line(0, 5, SCREEN_W, 5, &[16, 0, 0]);
+
+        let mut strict = Screen::in_memory();
+        strict.draw_line(0, 5, SCREEN_W, 5, &[16, 0, 0]);
+        flood_fill(&mut strict, 0, 0, 0, Connectivity::Four, &NamedColor::Blue);
+        assert_eq!(count(&strict, (0, 0, 248)), SCREEN_W * 5);
+
+        flood_fill(&mut screen, 0, 0, 16, Connectivity::Four, &NamedColor::Blue);
+        assert_eq!(count(&screen, (0, 0, 248)), SCREEN_W * SCREEN_H);
+    }
+
+    #[test]
+    fn paints_each_pixel_once() {
+        // Black still matches black, and half white is only blended in once.
+        let mut screen = Screen::in_memory();
+        flood_fill(&mut screen, 3, 3, 0, Connectivity::Eight, &NamedColor::Black);
+        assert_eq!(count(&screen, (0, 0, 0)), SCREEN_W * SCREEN_H);
+
+        flood_fill(&mut screen, 3, 3, 255, Connectivity::Four, &[255, 255, 255, 128]);
+        assert_eq!(count(&screen, screen.get_px(0, 0)), SCREEN_W * SCREEN_H);
+        assert_eq!(screen.get_px(0, 0), (120, 124, 120));
+    }
+
+    #[test]
+    fn ignores_seeds_off_screen() {
+        let mut screen = Screen::in_memory();
+        flood_fill(&mut screen, SCREEN_W, 0, 0, Connectivity::Four, &NamedColor::Red);
+        flood_fill(&mut screen, 0, SCREEN_H, 0, Connectivity::Four, &NamedColor::Red);
+        assert_eq!(count(&screen, (0, 0, 0)), SCREEN_W * SCREEN_H);
+    }
+}
@@ -1,4 +1,10 @@
 pub mod screen;
 pub mod color;
+pub mod paint;
+pub mod pattern;
 mod bresenham;
-pub mod text;
\ No newline at end of file
+mod polygon;
+pub mod flood;
+pub mod effects;
+pub mod text;
+pub mod theme;
\ No newline at end of file
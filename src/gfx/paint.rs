@@ -0,0 +1,144 @@
+use crate::gfx::color::{Colorful, RGBA};
+use crate::gfx::color::model::Interpolate;
+
+/// Trait for anything that can decide the color of a pixel given where that pixel is. Every
+/// `Colorful` is a `Paint` that ignores the coordinate, so solid colors and cycles can be passed
+/// anywhere a `Paint` is expected. Like `Colorful`, paints can be shared between threads.
+pub trait Paint: Send + Sync {
+    /// Returns the color to use at the given screen coordinate.
+    fn paint_at(&self, x: usize, y: usize) -> RGBA;
+}
+
+impl<T: Colorful + ?Sized> Paint for T {
+    #[inline]
+    fn paint_at(&self, _x: usize, _y: usize) -> RGBA {
+        self.as_rgba()
+    }
+}
+
+/// An ordered list of colors at offsets between 0.0 and 1.0. Shared by all the gradients.
+#[derive(Clone, Default)]
+pub struct ColorStops {
+    stops: Vec<(f32, RGBA)>,
+}
+
+impl ColorStops {
+    /// Adds a stop, keeping the list sorted by offset. Offsets are clamped to 0.0..=1.0.
+    pub fn push(&mut self, offset: f32, color: &impl Colorful) {
+        let offset = offset.clamp(0.0, 1.0);
+        let idx = self.stops.partition_point(|(o, _)| *o <= offset);
+        self.stops.insert(idx, (offset, color.as_rgba()));
+    }
+
+    /// Returns the color at `t`. Values outside of the first and last stop use the color of that
+    /// stop (the "pad" behaviour from CSS/SVG).
+    pub fn sample(&self, t: f32) -> RGBA {
+        let (first, last) = match (self.stops.first(), self.stops.last()) {
+            (Some(first), Some(last)) => (first, last),
+            _ => return (0, 0, 0, 0),
+        };
+
+        if t <= first.0 {
+            return first.1;
+        }
+        if t >= last.0 {
+            return last.1;
+        }
+
+        let idx = self.stops.partition_point(|(o, _)| *o <= t);
+        let (o0, c0) = self.stops[idx - 1];
+        let (o1, c1) = self.stops[idx];
+        let span = o1 - o0;
+        if span <= f32::EPSILON {
+            return c1;
+        }
+
+        c0.interpolate(&c1, (t - o0) / span)
+    }
+}
+
+/// A gradient that changes color along the line from `start` to `end`. Pixels are projected onto
+/// the line, so every pixel on a line perpendicular to it gets the same color.
+pub struct LinearGradient {
+    start: (f32, f32),
+    /// Direction vector divided by its squared length, so a dot product gives `t` directly.
+    axis: (f32, f32),
+    stops: ColorStops,
+}
+
+impl LinearGradient {
+    pub fn new(start: (f32, f32), end: (f32, f32)) -> Self {
+        let (dx, dy) = (end.0 - start.0, end.1 - start.1);
+        let len_sq = dx * dx + dy * dy;
+        let axis = if len_sq > 0.0 { (dx / len_sq, dy / len_sq) } else { (0.0, 0.0) };
+        Self { start, axis, stops: ColorStops::default() }
+    }
+
+    /// Adds a color stop at `offset` along the gradient line.
+    pub fn with_stop(mut self, offset: f32, color: &impl Colorful) -> Self {
+        self.stops.push(offset, color);
+        self
+    }
+}
+
+impl Paint for LinearGradient {
+    fn paint_at(&self, x: usize, y: usize) -> RGBA {
+        let t = (x as f32 - self.start.0) * self.axis.0 + (y as f32 - self.start.1) * self.axis.1;
+        self.stops.sample(t)
+    }
+}
+
+/// A gradient that radiates outward from `center`, reaching its last stop at `radius`.
+pub struct RadialGradient {
+    center: (f32, f32),
+    radius: f32,
+    stops: ColorStops,
+}
+
+impl RadialGradient {
+    pub fn new(center: (f32, f32), radius: f32) -> Self {
+        Self { center, radius: radius.max(f32::EPSILON), stops: ColorStops::default() }
+    }
+
+    /// Adds a color stop at `offset`, where 0.0 is the center and 1.0 is the radius.
+    pub fn with_stop(mut self, offset: f32, color: &impl Colorful) -> Self {
+        self.stops.push(offset, color);
+        self
+    }
+}
+
+impl Paint for RadialGradient {
+    fn paint_at(&self, x: usize, y: usize) -> RGBA {
+        let (dx, dy) = (x as f32 - self.center.0, y as f32 - self.center.1);
+        self.stops.sample((dx * dx + dy * dy).sqrt() / self.radius)
+    }
+}
+
+/// A gradient that sweeps around `center`, like the hands of a clock. `start_angle` is in radians,
+/// where 0.0 points right and angles increase clockwise (because screen Y points down).
+pub struct ConicGradient {
+    center: (f32, f32),
+    start_angle: f32,
+    stops: ColorStops,
+}
+
+impl ConicGradient {
+    pub fn new(center: (f32, f32), start_angle: f32) -> Self {
+        Self { center, start_angle, stops: ColorStops::default() }
+    }
+
+    /// Adds a color stop at `offset`, where 0.0 and 1.0 are both at `start_angle`. Repeat the first
+    /// color as the last stop for a seamless sweep.
+    pub fn with_stop(mut self, offset: f32, color: &impl Colorful) -> Self {
+        self.stops.push(offset, color);
+        self
+    }
+}
+
+impl Paint for ConicGradient {
+    fn paint_at(&self, x: usize, y: usize) -> RGBA {
+        use std::f32::consts::TAU;
+        let angle = (y as f32 - self.center.1).atan2(x as f32 - self.center.0) - self.start_angle;
+        self.stops.sample(angle.rem_euclid(TAU) / TAU)
+    }
+}
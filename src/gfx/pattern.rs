@@ -0,0 +1,64 @@
+use crate::gfx::color::{Colorful, RGBA};
+use crate::gfx::paint::Paint;
+
+/// A paint that repeats an RGBA image across the screen, like wallpaper. The image is anchored to
+/// the screen rather than to the shape being drawn, so neighbouring shapes line up seamlessly.
+pub struct Pattern {
+    /// Pixels in `[r, g, b, a, r, g, b, a, ...]` format, as returned by `decode_image`.
+    data: Vec<u8>,
+    width: usize,
+    height: usize,
+    offset: (i32, i32),
+    scale: f32,
+}
+
+impl Pattern {
+    /// Creates a pattern from RGBA image data that is `width` pixels wide.
+    pub fn new(data: Vec<u8>, width: usize) -> Self {
+        let height = (data.len() / 4).checked_div(width).unwrap_or(0);
+        Self { data, width, height, offset: (0, 0), scale: 1.0 }
+    }
+
+    /// Creates a checkerboard of `cell` sized squares, starting with `a` in the top left.
+    pub fn checkerboard(cell: usize, a: &impl Colorful, b: &impl Colorful) -> Self {
+        let cell = cell.max(1);
+        let (a, b) = (a.as_rgba(), b.as_rgba());
+        let mut data = Vec::with_capacity(cell * cell * 16);
+        for y in 0..cell * 2 {
+            for x in 0..cell * 2 {
+                let (r, g, b, a) = if (x / cell + y / cell).is_multiple_of(2) { a } else { b };
+                data.extend_from_slice(&[r, g, b, a]);
+            }
+        }
+        Self::new(data, cell * 2)
+    }
+
+    /// Moves the pattern by the given number of screen pixels.
+    pub fn with_offset(mut self, x: i32, y: i32) -> Self {
+        self.offset = (x, y);
+        self
+    }
+
+    /// Scales the pattern up or down. A scale of 2.0 draws every image pixel as a 2x2 block.
+    pub fn with_scale(mut self, scale: f32) -> Self {
+        self.scale = scale.max(f32::EPSILON);
+        self
+    }
+}
+
+impl Paint for Pattern {
+    fn paint_at(&self, x: usize, y: usize) -> RGBA {
+        if self.width == 0 || self.height == 0 {
+            return (0, 0, 0, 0);
+        }
+
+        // Nearest neighbour sampling keeps textures crisp and is cheap.
+        let u = ((x as i32 - self.offset.0) as f32 / self.scale).floor() as i64;
+        let v = ((y as i32 - self.offset.1) as f32 / self.scale).floor() as i64;
+        let u = u.rem_euclid(self.width as i64) as usize;
+        let v = v.rem_euclid(self.height as i64) as usize;
+
+        let off = (v * self.width + u) * 4;
+        (self.data[off], self.data[off + 1], self.data[off + 2], self.data[off + 3])
+    }
+}
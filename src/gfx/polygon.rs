@@ -0,0 +1,57 @@
+use crate::gfx::paint::Paint;
+use crate::{Screen, SCREEN_H, SCREEN_W};
+
+/// Fills a polygon using the even-odd rule. Each row is sampled through the pixel centers, and the
+/// spans between pairs of edge crossings are filled.
+pub fn fill_polygon(screen: &mut Screen, points: &[(f32, f32)], color: &impl Paint) {
+    if points.len() < 3 {
+        return;
+    }
+
+    let min_y = points.iter().map(|p| p.1).fold(f32::MAX, f32::min).max(0.0);
+    let max_y = points.iter().map(|p| p.1).fold(f32::MIN, f32::max).min(SCREEN_H as f32);
+
+    let mut crossings = Vec::new();
+    for y in min_y.floor() as usize..max_y.ceil() as usize {
+        let sample_y = y as f32 + 0.5;
+
+        crossings.clear();
+        for (i, &(x0, y0)) in points.iter().enumerate() {
+            let (x1, y1) = points[(i + 1) % points.len()];
+            // Half-open test so a vertex shared by two edges is only counted once.
+            if (y0 <= sample_y) != (y1 <= sample_y) {
+                crossings.push(x0 + (sample_y - y0) / (y1 - y0) * (x1 - x0));
+            }
+        }
+        crossings.sort_by(|a, b| a.total_cmp(b));
+
+        for span in crossings.chunks_exact(2) {
+            let start = (span[0] - 0.5).ceil().max(0.0) as usize;
+            let end = ((span[1] - 0.5).floor() + 1.0).clamp(0.0, SCREEN_W as f32) as usize;
+            for x in start..end {
+                screen.blend_px(x, y, color);
+            }
+        }
+    }
+}
+
+/// Builds the outline of a line segment `width` pixels thick with round caps. Drawing each segment
+/// of a path this way gives round joins for free.
+pub fn stroke_outline(x0: f32, y0: f32, x1: f32, y1: f32, width: f32) -> Vec<(f32, f32)> {
+    const CAP_STEPS: usize = 8;
+    let radius = width / 2.0;
+
+    // Angle of the segment, so the caps can be swept around each end.
+    let angle = (y1 - y0).atan2(x1 - x0);
+    let half_turn = std::f32::consts::PI;
+
+    let mut points = Vec::with_capacity((CAP_STEPS + 1) * 2);
+    for (cx, cy, start) in [(x1, y1, angle - half_turn / 2.0), (x0, y0, angle + half_turn / 2.0)] {
+        for step in 0..=CAP_STEPS {
+            let a = start + half_turn * step as f32 / CAP_STEPS as f32;
+            points.push((cx + radius * a.cos(), cy + radius * a.sin()));
+        }
+    }
+
+    points
+}
@@ -3,15 +3,23 @@ use std::fs::File;
 use std::path::PathBuf;
 use itertools::Itertools;
 use memmap2::{MmapMut, MmapOptions};
-use crate::{BUFFER_SIZE, SCREEN_W};
-use crate::gfx::bresenham;
+use crate::{BUFFER_SIZE, SCREEN_H, SCREEN_W};
+use crate::gfx::{bresenham, effects, flood, polygon};
+use crate::gfx::effects::{Blur, Shadow};
+use crate::gfx::flood::Connectivity;
 use crate::gfx::color::Colorful;
+use crate::gfx::paint::Paint;
+use crate::gfx::text::Text;
 
 pub struct Screen {
     map: MmapMut
 }
 
 impl Screen {
+    /// Maps the framebuffer at `path`, such as `/dev/fb0`, into memory to draw on.
+    ///
+    /// # Safety
+    /// The file must not be truncated while the `Screen` exists, as with any memory-mapped file.
     pub unsafe fn new(path: PathBuf) -> Result<Self, Box<dyn Error>> {
         // TODO: use map_err() here to get better errors.
         let file = File::options().read(true).write(true).open(path)?;
@@ -24,6 +32,12 @@ impl Screen {
         Ok(Self { map })
     }
 
+    /// Creates a screen backed by memory rather than the framebuffer, for tests.
+    #[cfg(test)]
+    pub(crate) fn in_memory() -> Self {
+        Self { map: MmapOptions::new().len(BUFFER_SIZE).map_anon().unwrap() }
+    }
+
     /// Packs a 24-bit color (3 8-bit channels) into a 16-bit color.
     #[inline]
     fn color_to_16_bits(r: u8, g: u8, b: u8) -> (u8, u8) {
@@ -35,6 +49,16 @@ impl Screen {
         (h, l)
     }
 
+    /// Unpacks a 16-bit color back into 3 8-bit channels. The low bits of each channel are lost.
+    #[inline]
+    fn color_from_16_bits(h: u8, l: u8) -> (u8, u8, u8) {
+        let r = l & 0b1111_1000;
+        let g = ((l & 0b0000_0111) << 5) | ((h & 0b1110_0000) >> 3);
+        let b = (h & 0b0001_1111) << 3;
+
+        (r, g, b)
+    }
+
     /// Blends a color with alpha channel with an opaque color.
     fn blend(r: u8, g: u8, b: u8, a: u8, cr: u8, cg: u8, cb: u8) -> (u8, u8, u8) {
         let nr = (((a as u16 * r as u16) + ((255 - a as u16) * cr as u16)) / 256) as u8;
@@ -49,10 +73,9 @@ impl Screen {
         (SCREEN_W * 2 * y) + x * 2
     }
 
-    /// Sets a specified pixel to a color.
+    /// Writes an opaque 24-bit color to a specified pixel.
     #[inline]
-    fn set_px(&mut self, x: usize, y: usize, color: &impl Colorful) {
-        let (r, g, b, _a) = color.as_rgba();
+    fn write_px(&mut self, x: usize, y: usize, r: u8, g: u8, b: u8) {
         let (h, l) = Self::color_to_16_bits(r, g, b);
         let b_off = self.buffer_offset(x, y);
 
@@ -60,15 +83,30 @@ impl Screen {
         self.map[b_off + 1] = l;
     }
 
+    /// Returns the bytes of a specified pixel in the framebuffer's native format.
+    #[inline]
+    pub(crate) fn raw_px(&self, x: usize, y: usize) -> (u8, u8) {
+        let b_off = self.buffer_offset(x, y);
+        (self.map[b_off], self.map[b_off + 1])
+    }
+
+    /// Returns the color of a specified pixel as 3 8-bit channels.
+    #[inline]
+    pub fn get_px(&self, x: usize, y: usize) -> (u8, u8, u8) {
+        let (h, l) = self.raw_px(x, y);
+        Self::color_from_16_bits(h, l)
+    }
+
     /// Updates a specified pixel's color by blending it with its new color.
     /// https://en.wikipedia.org/wiki/Alpha_compositing#Alpha_blending
-    pub(crate) fn blend_px(&mut self, x: usize, y: usize, color: &impl Colorful) {
+    pub fn blend_px(&mut self, x: usize, y: usize, color: &impl Paint) {
         // alpha * new color + (1 - alpha) * prev color
-        let (r, g, b, a) = color.as_rgba();
+        let (r, g, b, a) = color.paint_at(x, y);
 
-        // Short-cut if pixel is fully opaque. Hot path in images.
+        // Short-cut if pixel is fully opaque. Hot path in images. Writes the color we already have
+        // rather than asking `color` again, since cycles would hand out a different one.
         if a == 255 {
-            self.set_px(x, y, color);
+            self.write_px(x, y, r, g, b);
             return;
         }
 
@@ -78,25 +116,34 @@ impl Screen {
         }
 
         // Retrieve the current the color
-        let b_off = self.buffer_offset(x, y);
-        let (ch, cl) = (self.map[b_off], self.map[b_off + 1]);
-
-        let cr = cl & 0b1111_1000;
-        let cg = ((cl & 0b0000_0111) << 5) | ((ch & 0b1110_0000) >> 3);
-        let cb = (ch & 0b0001_1111) << 3;
+        let (cr, cg, cb) = self.get_px(x, y);
 
         let (nr, ng, nb) = Self::blend(r, g, b, a, cr, cg, cb);
 
-        self.set_px(x, y, &[nr, ng, nb]);
+        self.write_px(x, y, nr, ng, nb);
     }
 
     /// Draws a line (kinda) from (x1, y1) to (x2, y2).
-    pub(crate) fn draw_line(&mut self, x1: usize, y1: usize, x2: usize, y2: usize, color: &impl Colorful) {
+    pub fn draw_line(&mut self, x1: usize, y1: usize, x2: usize, y2: usize, color: &impl Paint) {
         bresenham::draw_line(self, x1 as i32, y1 as i32, x2 as i32, y2 as i32, color);
     }
 
+    /// Draws a line `width` pixels thick with round ends from (x1, y1) to (x2, y2). Unlike
+    /// `draw_line`, every covered pixel is drawn exactly once, so patterns and gradients line up.
+    pub fn draw_stroke(&mut self, x1: usize, y1: usize, x2: usize, y2: usize, width: usize, color: &impl Paint) {
+        let outline = polygon::stroke_outline(x1 as f32 + 0.5, y1 as f32 + 0.5, x2 as f32 + 0.5, y2 as f32 + 0.5, width as f32);
+        polygon::fill_polygon(self, &outline, color);
+    }
+
+    /// Fills the polygon described by `points` using the even-odd rule. Points are in pixels, where
+    /// (0.5, 0.5) is the center of the top left pixel.
+    pub fn fill_polygon(&mut self, points: &[(f32, f32)], color: &impl Paint) {
+        polygon::fill_polygon(self, points, color);
+    }
+
     /// Draws a rectangle with rounded corners and a border.
-    pub(crate) fn draw_rect(&mut self, x: usize, y: usize, w: usize, h: usize, radius: usize, fill: &impl Colorful, border: &impl Colorful) {
+    #[allow(clippy::too_many_arguments)]
+    pub fn draw_rect(&mut self, x: usize, y: usize, w: usize, h: usize, radius: usize, fill: &impl Paint, border: &impl Paint) {
         const MASK_SIZE: usize = 8;
         const CORNER_MASK: [u8; MASK_SIZE * (MASK_SIZE + 1)] = [
             0, 0, 0, 0, 0, 0, 0, 0,
@@ -139,8 +186,14 @@ impl Screen {
         }
     }
 
+    /// Fills the connected region around (x, y) whose colors are within `tolerance` of the color at
+    /// (x, y). A `tolerance` of 0 only matches pixels with exactly the same framebuffer value.
+    pub fn flood_fill(&mut self, x: usize, y: usize, tolerance: u8, connectivity: Connectivity, color: &impl Paint) {
+        flood::flood_fill(self, x, y, tolerance, connectivity, color);
+    }
+
     /// Fills the entire framebuffer with a single color.
-    pub(crate) fn fill(&mut self, color: &impl Colorful) {
+    pub fn fill(&mut self, color: &impl Colorful) {
         let (r, g, b, _a) = color.as_rgba();
         // I am in 16 bit mode, so, I need to use 5 bits per pixel (I guess). I would prefer to set
         // 24-bit color mode.
@@ -151,24 +204,35 @@ impl Screen {
         }
     }
 
+    /// Fills the entire framebuffer with a paint that changes across the screen, such as a
+    /// gradient. Alpha is ignored. The paint is asked for every pixel, so a color cycle advances
+    /// once per pixel. Use `fill` for a single color, which is much faster.
+    pub fn fill_paint(&mut self, paint: &impl Paint) {
+        for i in 0..SCREEN_W * SCREEN_H {
+            let (r, g, b, _a) = paint.paint_at(i % SCREEN_W, i / SCREEN_W);
+            let (h, l) = Self::color_to_16_bits(r, g, b);
+            self.map[i * 2] = h;
+            self.map[i * 2 + 1] = l;
+        }
+    }
+
     /// Copies the provided image data in `[r, g, b, a, r, g, b, a, ...]` format to the screen's
     /// current color space, for use with `blit`. The `image` crate's `DynamicImage::as_rgba8()`
     /// function provides the correct format for this.
-    pub(crate) fn render_image(&self, data: &[u8], background: &impl Colorful) -> Vec<u8> {
+    pub fn render_image(&self, data: &[u8], background: &impl Colorful) -> Vec<u8> {
         let (br, bg, bb, _) = background.as_rgba();
         let (hi, lo): (Vec<_>, Vec<_>) = data.chunks(4)
-            .into_iter()
             .map(|n| Self::blend(n[0], n[1], n[2], n[3], br, bg, bb))
             .map(|(r, g, b)| Self::color_to_16_bits(r, g, b))
             .unzip();
 
-        hi.into_iter().interleave(lo.into_iter()).collect()
+        hi.into_iter().interleave(lo).collect()
     }
 
     /// Draws the provided texture to the screen at the given coordinate and width. Blitting
     /// pre-rendered text is the preferred way to display text. `data` is expected to be in the
     /// correct format for the buffer. Use `render` to prepare images for this.
-    pub(crate) fn blit_image(&mut self, x: usize, y: usize, w: usize, data: &[u8]) {
+    pub fn blit_image(&mut self, x: usize, y: usize, w: usize, data: &[u8]) {
         for (idx, &byte) in data.iter().enumerate() {
             self.map[(x * 2) + idx % (w * 2) + idx / (w * 2) * SCREEN_W * 2 + (y * SCREEN_W * 2)] = byte;
         }
@@ -176,10 +240,135 @@ impl Screen {
 
     /// More expensive image copy call that blends all the pixels together. Needed for images with
     /// partial transparency that can't be pre-blended with a fixed color.
-    pub(crate) fn blend_image(&mut self, x: usize, y: usize, w: usize, data: &[u8]) {
-        for (idx, chunk) in data.chunks(4).into_iter().enumerate() {
+    pub fn blend_image(&mut self, x: usize, y: usize, w: usize, data: &[u8]) {
+        for (idx, chunk) in data.chunks(4).enumerate() {
             let rgba = [chunk[0], chunk[1], chunk[2], chunk[3]];
             self.blend_px(x + idx % w, y + (idx / w), &rgba);
         }
     }
-}
\ No newline at end of file
+
+    /// Draws text straight onto the screen, blending the edges of each glyph with whatever is
+    /// already there. The text is drawn in `color`, whatever color it was rendered with, except for
+    /// color glyphs such as emoji, which keep their own colors. Rich text and text with an outline
+    /// or shadow keep all of their colors, so `color` isn't used for them at all. Text rendered for
+    /// an LCD blends each subpixel separately. Parts of the text that are off screen are ignored.
+    pub fn draw_text(&mut self, x: usize, y: usize, text: &Text, color: &impl Paint) {
+        let w = text.width.min(SCREEN_W.saturating_sub(x));
+        let h = text.height().min(SCREEN_H.saturating_sub(y));
+
+        for j in 0..h {
+            for i in 0..w {
+                let idx = j * text.width + i;
+                let coverage = text.coverage[idx];
+                if let Some(lcd) = &text.lcd {
+                    // Each subpixel gets its own coverage, so blend the channels separately.
+                    if coverage > 0 {
+                        let (r, g, b, a) = color.paint_at(x + i, y + j);
+                        let (br, bg, bb) = self.get_px(x + i, y + j);
+                        let mix = |new: u8, old: u8, cov: u8| {
+                            let alpha = cov as u32 * a as u32 / u8::MAX as u32;
+                            ((new as u32 * alpha + old as u32 * (u8::MAX as u32 - alpha)) / u8::MAX as u32) as u8
+                        };
+                        let sub = &lcd[idx * 3..idx * 3 + 3];
+                        self.write_px(x + i, y + j, mix(r, br, sub[0]), mix(g, bg, sub[1]), mix(b, bb, sub[2]));
+                    }
+                } else if coverage > 0 {
+                    let (r, g, b, a) = color.paint_at(x + i, y + j);
+                    let alpha = (coverage as u16 * a as u16 / u8::MAX as u16) as u8;
+                    self.blend_px(x + i, y + j, &[r, g, b, alpha]);
+                }
+                if let Some(layer) = &text.color_glyphs {
+                    let px = &layer[idx * 4..idx * 4 + 4];
+                    self.blend_px(x + i, y + j, &[px[0], px[1], px[2], px[3]]);
+                }
+            }
+        }
+    }
+
+    /// Blurs a region of the screen in place. Handy for frosted-glass panels behind dialogs. Parts
+    /// of the region that are off screen are ignored.
+    pub fn blur_region(&mut self, x: usize, y: usize, w: usize, h: usize, blur: Blur) {
+        let w = w.min(SCREEN_W.saturating_sub(x));
+        let h = h.min(SCREEN_H.saturating_sub(y));
+
+        let mut rgb = Vec::with_capacity(w * h * 3);
+        for j in 0..h {
+            for i in 0..w {
+                let (r, g, b) = self.get_px(x + i, y + j);
+                rgb.extend_from_slice(&[r, g, b]);
+            }
+        }
+
+        effects::blur(&mut rgb, w, h, 3, blur);
+
+        for (idx, px) in rgb.chunks(3).enumerate() {
+            self.write_px(x + idx % w, y + idx / w, px[0], px[1], px[2]);
+        }
+    }
+
+    /// Draws the shadow of a `w` pixels wide alpha mask whose shape is drawn at (x, y). Draw the
+    /// shadow first, then the shape on top of it.
+    pub fn draw_shadow(&mut self, x: usize, y: usize, w: usize, mask: &[u8], shadow: &Shadow) {
+        if w == 0 {
+            return;
+        }
+        let (data, sw, sh) = shadow.render_mask(mask, w, mask.len() / w);
+        let (r, g, b, a) = shadow.color;
+        let sx = x as i32 - shadow.margin() as i32 + shadow.offset.0;
+        let sy = y as i32 - shadow.margin() as i32 + shadow.offset.1;
+
+        for j in 0..sh {
+            let py = sy + j as i32;
+            if py < 0 || py >= SCREEN_H as i32 {
+                continue;
+            }
+            for i in 0..sw {
+                let px = sx + i as i32;
+                let coverage = data[j * sw + i];
+                if px < 0 || px >= SCREEN_W as i32 || coverage == 0 {
+                    continue;
+                }
+                let alpha = (coverage as u16 * a as u16 / u8::MAX as u16) as u8;
+                self.blend_px(px as usize, py as usize, &[r, g, b, alpha]);
+            }
+        }
+    }
+
+    /// Draws the shadow of a rectangle drawn with `draw_rect` using the same arguments.
+    pub fn draw_rect_shadow(&mut self, x: usize, y: usize, w: usize, h: usize, radius: usize, shadow: &Shadow) {
+        self.draw_shadow(x, y, w, &effects::rect_mask(w, h, radius), shadow);
+    }
+
+    /// Draws the shadow of an RGBA image, such as a `decode_image` result or a rendered `Text`
+    /// bitmap, which is drawn at (x, y) with `blend_image`.
+    pub fn draw_image_shadow(&mut self, x: usize, y: usize, w: usize, data: &[u8], shadow: &Shadow) {
+        self.draw_shadow(x, y, w, &effects::alpha_mask(data), shadow);
+    }
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gfx::color::ColorfulCycle;
+
+    fn red_and_blue() -> ColorfulCycle {
+        ColorfulCycle::new([(255, 0, 0, 255), (0, 0, 255, 255)].into_iter().cycle())
+    }
+
+    #[test]
+    fn fill_uses_one_color() {
+        let mut screen = Screen::in_memory();
+        screen.fill(&red_and_blue());
+        assert_eq!(screen.get_px(0, 0), (248, 0, 0));
+        assert_eq!(screen.get_px(1, 0), (248, 0, 0));
+        assert_eq!(screen.get_px(SCREEN_W - 1, SCREEN_H - 1), (248, 0, 0));
+    }
+
+    #[test]
+    fn fill_paint_asks_every_pixel() {
+        let mut screen = Screen::in_memory();
+        screen.fill_paint(&red_and_blue());
+        assert_eq!(screen.get_px(0, 0), (248, 0, 0));
+        assert_eq!(screen.get_px(1, 0), (0, 0, 248));
+        assert_eq!(screen.get_px(SCREEN_W - 1, SCREEN_H - 1), (0, 0, 248));
+    }
+}
@@ -0,0 +1,98 @@
+/// Order of the red, green and blue subpixels across each pixel of the display.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum SubpixelOrder {
+    Rgb,
+    Bgr,
+}
+
+/// How `TextRenderer` turns glyph outlines into pixels.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Rendering {
+    /// Places glyphs at quarter pixel positions, so spacing stays even. Turning it off snaps every
+    /// glyph to a whole pixel, which keeps repeated glyphs identical but spaces them unevenly.
+    pub subpixel_positioning: bool,
+    /// Coverage is raised to the power of `1 / gamma`. Above 1.0 makes anti-aliased edges heavier,
+    /// which helps thin fonts that look faint once blended. 1.0 leaves coverage as rasterized.
+    pub gamma: f32,
+    /// Thickens strokes by up to this many pixels, from 0.0 to 1.0, like FreeType's stem
+    /// darkening. Keeps hairline fonts legible at small sizes. With `lcd`, strokes thicken by
+    /// subpixels rather than whole pixels horizontally.
+    pub stem_darkening: f32,
+    /// Rasterizes at three times the horizontal resolution and gives each subpixel its own
+    /// coverage, for sharper text on LCDs with a known subpixel order. Only used by
+    /// `Screen::draw_text`; other uses get ordinary anti-aliasing.
+    pub lcd: Option<SubpixelOrder>,
+}
+
+impl Default for Rendering {
+    fn default() -> Self {
+        Self { subpixel_positioning: true, gamma: 1.0, stem_darkening: 0.0, lcd: None }
+    }
+}
+
+/// Weights of the filter that spreads each subpixel's coverage over its neighbours, so LCD text
+/// doesn't get colored fringes. The same as FreeType's default filter.
+const LCD_FILTER: [u16; 5] = [8, 77, 86, 77, 8];
+
+impl Rendering {
+    /// Applies stem darkening then gamma to a coverage mask `w` pixels wide.
+    pub(crate) fn adjust(&self, mask: &mut [u8], w: usize) {
+        self.apply_darkening(mask, w);
+        self.apply_gamma(mask);
+    }
+
+    /// Thickens strokes in a coverage mask `w` pixels wide by `stem_darkening`. Masks should have
+    /// a pixel of room on every side for strokes to grow into.
+    pub(crate) fn apply_darkening(&self, mask: &mut [u8], w: usize) {
+        if self.stem_darkening > 0.0 && w > 0 {
+            darken(mask, w, self.stem_darkening);
+        }
+    }
+
+    /// Raises coverage to the power of `1 / gamma`.
+    pub(crate) fn apply_gamma(&self, mask: &mut [u8]) {
+        if self.gamma == 1.0 || self.gamma <= 0.0 {
+            return;
+        }
+        let table: Vec<u8> = (0..=u8::MAX)
+            .map(|v| ((v as f32 / u8::MAX as f32).powf(1.0 / self.gamma) * u8::MAX as f32).round() as u8)
+            .collect();
+        for v in mask.iter_mut() {
+            *v = table[*v as usize];
+        }
+    }
+}
+
+/// Thickens strokes by raising each pixel's coverage towards full, by `amount` of its strongest
+/// neighbour's coverage.
+fn darken(mask: &mut [u8], w: usize, amount: f32) {
+    let h = mask.len() / w;
+    let source = mask.to_vec();
+    let at = |x: usize, y: usize| source[y * w + x] as f32;
+
+    for y in 0..h {
+        for x in 0..w {
+            let neighbours = [
+                (x > 0).then(|| at(x - 1, y)),
+                (x + 1 < w).then(|| at(x + 1, y)),
+                (y > 0).then(|| at(x, y - 1)),
+                (y + 1 < h).then(|| at(x, y + 1)),
+            ];
+            let strongest = neighbours.into_iter().flatten().fold(0.0, f32::max);
+            let v = at(x, y);
+            mask[y * w + x] = (v + (u8::MAX as f32 - v) * amount * strongest / u8::MAX as f32) as u8;
+        }
+    }
+}
+
+/// Filters a row of subpixel coverage in place with `LCD_FILTER`.
+pub(crate) fn lcd_filter(row: &mut [u8]) {
+    let source = row.to_vec();
+    let total: u16 = LCD_FILTER.iter().sum();
+    for (i, v) in row.iter_mut().enumerate() {
+        let sum: u32 = LCD_FILTER.iter().enumerate()
+            .filter_map(|(k, &weight)| (i + k).checked_sub(2).and_then(|j| source.get(j)).map(|&s| s as u32 * weight as u32))
+            .sum();
+        *v = (sum / total as u32).min(u8::MAX as u32) as u8;
+    }
+}
@@ -0,0 +1,650 @@
+use std::collections::HashMap;
+use std::error::Error;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::OnceLock;
+use crate::gfx::color::Colorful;
+use crate::gfx::text::{layout, Paragraph, Text};
+use crate::gfx::text::layout::{InkBounds, ParagraphStyle, TextMetrics};
+
+/// Rows of the built-in font's glyphs for ASCII 0x20 (space) to 0x7E (`~`). Each row is 5 pixels,
+/// with the leftmost pixel in the highest bit.
+const BUILTIN_GLYPHS: [[u8; 7]; 95] = [
+    [0b00000, 0b00000, 0b00000, 0b00000, 0b00000, 0b00000, 0b00000], // ' '
+    [0b00100, 0b00100, 0b00100, 0b00100, 0b00000, 0b00000, 0b00100], // '!'
+    [0b01010, 0b01010, 0b01010, 0b00000, 0b00000, 0b00000, 0b00000], // '"'
+    [0b01010, 0b01010, 0b11111, 0b01010, 0b11111, 0b01010, 0b01010], // '#'
+    [0b00100, 0b01111, 0b10100, 0b01110, 0b00101, 0b11110, 0b00100], // '$'
+    [0b11000, 0b11001, 0b00010, 0b00100, 0b01000, 0b10011, 0b00011], // '%'
+    [0b01100, 0b10010, 0b10100, 0b01000, 0b10101, 0b10010, 0b01101], // '&'
+    [0b01100, 0b00100, 0b01000, 0b00000, 0b00000, 0b00000, 0b00000], // '\''
+    [0b00010, 0b00100, 0b01000, 0b01000, 0b01000, 0b00100, 0b00010], // '('
+    [0b01000, 0b00100, 0b00010, 0b00010, 0b00010, 0b00100, 0b01000], // ')'
+    [0b00000, 0b00100, 0b10101, 0b01110, 0b10101, 0b00100, 0b00000], // '*'
+    [0b00000, 0b00100, 0b00100, 0b11111, 0b00100, 0b00100, 0b00000], // '+'
+    [0b00000, 0b00000, 0b00000, 0b00000, 0b01100, 0b00100, 0b01000], // ','
+    [0b00000, 0b00000, 0b00000, 0b11111, 0b00000, 0b00000, 0b00000], // '-'
+    [0b00000, 0b00000, 0b00000, 0b00000, 0b00000, 0b01100, 0b01100], // '.'
+    [0b00000, 0b00001, 0b00010, 0b00100, 0b01000, 0b10000, 0b00000], // '/'
+    [0b01110, 0b10001, 0b10011, 0b10101, 0b11001, 0b10001, 0b01110], // '0'
+    [0b00100, 0b01100, 0b00100, 0b00100, 0b00100, 0b00100, 0b01110], // '1'
+    [0b01110, 0b10001, 0b00001, 0b00010, 0b00100, 0b01000, 0b11111], // '2'
+    [0b11111, 0b00010, 0b00100, 0b00010, 0b00001, 0b10001, 0b01110], // '3'
+    [0b00010, 0b00110, 0b01010, 0b10010, 0b11111, 0b00010, 0b00010], // '4'
+    [0b11111, 0b10000, 0b11110, 0b00001, 0b00001, 0b10001, 0b01110], // '5'
+    [0b00110, 0b01000, 0b10000, 0b11110, 0b10001, 0b10001, 0b01110], // '6'
+    [0b11111, 0b00001, 0b00010, 0b00100, 0b01000, 0b01000, 0b01000], // '7'
+    [0b01110, 0b10001, 0b10001, 0b01110, 0b10001, 0b10001, 0b01110], // '8'
+    [0b01110, 0b10001, 0b10001, 0b01111, 0b00001, 0b00010, 0b01100], // '9'
+    [0b00000, 0b01100, 0b01100, 0b00000, 0b01100, 0b01100, 0b00000], // ':'
+    [0b00000, 0b01100, 0b01100, 0b00000, 0b01100, 0b00100, 0b01000], // ';'
+    [0b00010, 0b00100, 0b01000, 0b10000, 0b01000, 0b00100, 0b00010], // '<'
+    [0b00000, 0b00000, 0b11111, 0b00000, 0b11111, 0b00000, 0b00000], // '='
+    [0b01000, 0b00100, 0b00010, 0b00001, 0b00010, 0b00100, 0b01000], // '>'
+    [0b01110, 0b10001, 0b00001, 0b00010, 0b00100, 0b00000, 0b00100], // '?'
+    [0b01110, 0b10001, 0b00001, 0b01101, 0b10101, 0b10101, 0b01110], // '@'
+    [0b01110, 0b10001, 0b10001, 0b10001, 0b11111, 0b10001, 0b10001], // 'A'
+    [0b11110, 0b10001, 0b10001, 0b11110, 0b10001, 0b10001, 0b11110], // 'B'
+    [0b01110, 0b10001, 0b10000, 0b10000, 0b10000, 0b10001, 0b01110], // 'C'
+    [0b11100, 0b10010, 0b10001, 0b10001, 0b10001, 0b10010, 0b11100], // 'D'
+    [0b11111, 0b10000, 0b10000, 0b11110, 0b10000, 0b10000, 0b11111], // 'E'
+    [0b11111, 0b10000, 0b10000, 0b11110, 0b10000, 0b10000, 0b10000], // 'F'
+    [0b01110, 0b10001, 0b10000, 0b10111, 0b10001, 0b10001, 0b01111], // 'G'
+    [0b10001, 0b10001, 0b10001, 0b11111, 0b10001, 0b10001, 0b10001], // 'H'
+    [0b01110, 0b00100, 0b00100, 0b00100, 0b00100, 0b00100, 0b01110], // 'I'
+    [0b00111, 0b00010, 0b00010, 0b00010, 0b00010, 0b10010, 0b01100], // 'J'
+    [0b10001, 0b10010, 0b10100, 0b11000, 0b10100, 0b10010, 0b10001], // 'K'
+    [0b10000, 0b10000, 0b10000, 0b10000, 0b10000, 0b10000, 0b11111], // 'L'
+    [0b10001, 0b11011, 0b10101, 0b10101, 0b10001, 0b10001, 0b10001], // 'M'
+    [0b10001, 0b10001, 0b11001, 0b10101, 0b10011, 0b10001, 0b10001], // 'N'
+    [0b01110, 0b10001, 0b10001, 0b10001, 0b10001, 0b10001, 0b01110], // 'O'
+    [0b11110, 0b10001, 0b10001, 0b11110, 0b10000, 0b10000, 0b10000], // 'P'
+    [0b01110, 0b10001, 0b10001, 0b10001, 0b10101, 0b10010, 0b01101], // 'Q'
+    [0b11110, 0b10001, 0b10001, 0b11110, 0b10100, 0b10010, 0b10001], // 'R'
+    [0b01111, 0b10000, 0b10000, 0b01110, 0b00001, 0b00001, 0b11110], // 'S'
+    [0b11111, 0b00100, 0b00100, 0b00100, 0b00100, 0b00100, 0b00100], // 'T'
+    [0b10001, 0b10001, 0b10001, 0b10001, 0b10001, 0b10001, 0b01110], // 'U'
+    [0b10001, 0b10001, 0b10001, 0b10001, 0b10001, 0b01010, 0b00100], // 'V'
+    [0b10001, 0b10001, 0b10001, 0b10101, 0b10101, 0b10101, 0b01010], // 'W'
+    [0b10001, 0b10001, 0b01010, 0b00100, 0b01010, 0b10001, 0b10001], // 'X'
+    [0b10001, 0b10001, 0b10001, 0b01010, 0b00100, 0b00100, 0b00100], // 'Y'
+    [0b11111, 0b00001, 0b00010, 0b00100, 0b01000, 0b10000, 0b11111], // 'Z'
+    [0b01110, 0b01000, 0b01000, 0b01000, 0b01000, 0b01000, 0b01110], // '['
+    [0b00000, 0b10000, 0b01000, 0b00100, 0b00010, 0b00001, 0b00000], // '\\'
+    [0b01110, 0b00010, 0b00010, 0b00010, 0b00010, 0b00010, 0b01110], // ']'
+    [0b00100, 0b01010, 0b10001, 0b00000, 0b00000, 0b00000, 0b00000], // '^'
+    [0b00000, 0b00000, 0b00000, 0b00000, 0b00000, 0b00000, 0b11111], // '_'
+    [0b01000, 0b00100, 0b00010, 0b00000, 0b00000, 0b00000, 0b00000], // '`'
+    [0b00000, 0b00000, 0b01110, 0b00001, 0b01111, 0b10001, 0b01111], // 'a'
+    [0b10000, 0b10000, 0b10110, 0b11001, 0b10001, 0b10001, 0b11110], // 'b'
+    [0b00000, 0b00000, 0b01110, 0b10000, 0b10000, 0b10001, 0b01110], // 'c'
+    [0b00001, 0b00001, 0b01101, 0b10011, 0b10001, 0b10001, 0b01111], // 'd'
+    [0b00000, 0b00000, 0b01110, 0b10001, 0b11111, 0b10000, 0b01110], // 'e'
+    [0b00110, 0b01001, 0b01000, 0b11100, 0b01000, 0b01000, 0b01000], // 'f'
+    [0b00000, 0b01111, 0b10001, 0b10001, 0b01111, 0b00001, 0b01110], // 'g'
+    [0b10000, 0b10000, 0b10110, 0b11001, 0b10001, 0b10001, 0b10001], // 'h'
+    [0b00100, 0b00000, 0b01100, 0b00100, 0b00100, 0b00100, 0b01110], // 'i'
+    [0b00010, 0b00000, 0b00110, 0b00010, 0b00010, 0b10010, 0b01100], // 'j'
+    [0b10000, 0b10000, 0b10010, 0b10100, 0b11000, 0b10100, 0b10010], // 'k'
+    [0b01100, 0b00100, 0b00100, 0b00100, 0b00100, 0b00100, 0b01110], // 'l'
+    [0b00000, 0b00000, 0b11010, 0b10101, 0b10101, 0b10001, 0b10001], // 'm'
+    [0b00000, 0b00000, 0b10110, 0b11001, 0b10001, 0b10001, 0b10001], // 'n'
+    [0b00000, 0b00000, 0b01110, 0b10001, 0b10001, 0b10001, 0b01110], // 'o'
+    [0b00000, 0b00000, 0b11110, 0b10001, 0b11110, 0b10000, 0b10000], // 'p'
+    [0b00000, 0b00000, 0b01101, 0b10011, 0b01111, 0b00001, 0b00001], // 'q'
+    [0b00000, 0b00000, 0b10110, 0b11001, 0b10000, 0b10000, 0b10000], // 'r'
+    [0b00000, 0b00000, 0b01110, 0b10000, 0b01110, 0b00001, 0b11110], // 's'
+    [0b01000, 0b01000, 0b11100, 0b01000, 0b01000, 0b01001, 0b00110], // 't'
+    [0b00000, 0b00000, 0b10001, 0b10001, 0b10001, 0b10011, 0b01101], // 'u'
+    [0b00000, 0b00000, 0b10001, 0b10001, 0b10001, 0b01010, 0b00100], // 'v'
+    [0b00000, 0b00000, 0b10001, 0b10001, 0b10101, 0b10101, 0b01010], // 'w'
+    [0b00000, 0b00000, 0b10001, 0b01010, 0b00100, 0b01010, 0b10001], // 'x'
+    [0b00000, 0b00000, 0b10001, 0b10001, 0b01111, 0b00001, 0b01110], // 'y'
+    [0b00000, 0b00000, 0b11111, 0b00010, 0b00100, 0b01000, 0b11111], // 'z'
+    [0b00010, 0b00100, 0b00100, 0b01000, 0b00100, 0b00100, 0b00010], // '{'
+    [0b00100, 0b00100, 0b00100, 0b00100, 0b00100, 0b00100, 0b00100], // '|'
+    [0b01000, 0b00100, 0b00100, 0b00010, 0b00100, 0b00100, 0b01000], // '}'
+    [0b00000, 0b00000, 0b00000, 0b01101, 0b10010, 0b00000, 0b00000], // '~'
+];
+
+/// Magic numbers at the start of PSF version 1 and 2 files.
+const PSF1_MAGIC: [u8; 2] = [0x36, 0x04];
+const PSF2_MAGIC: [u8; 4] = [0x72, 0xb5, 0x4a, 0x86];
+
+/// Largest glyph width, height or offset accepted from a font file, in pixels. Console and X11
+/// fonts are far smaller, so anything bigger is a broken file rather than a big font.
+const MAX_GLYPH_SIZE: i32 = 1024;
+
+/// Whether a size or offset read from a font file is within `MAX_GLYPH_SIZE` either way.
+fn in_range(value: i32) -> bool {
+    (-MAX_GLYPH_SIZE..=MAX_GLYPH_SIZE).contains(&value)
+}
+
+/// One glyph of a bitmap font.
+struct BitmapGlyph {
+    width: usize,
+    /// Offset of the left of the bitmap from the pen position.
+    left: i32,
+    /// Offset of the top of the bitmap from the baseline. Negative is above the baseline.
+    top: i32,
+    /// How far the pen moves after drawing this glyph.
+    advance: usize,
+    /// Whether each pixel is set, row by row.
+    pixels: Vec<bool>,
+}
+
+/// A font made of pixels rather than outlines. Bitmap fonts are crisp at their own size and scale
+/// up by whole multiples, and don't need `rusttype`.
+pub struct BitmapFont {
+    glyphs: HashMap<char, BitmapGlyph>,
+    /// Pixels above the baseline.
+    ascent: usize,
+    /// Pixels below the baseline.
+    descent: usize,
+}
+
+impl BitmapFont {
+    /// A small 5x7 pixel font covering printable ASCII, in an 6x8 cell.
+    pub fn builtin() -> Self {
+        let glyphs = BUILTIN_GLYPHS.iter().enumerate().map(|(idx, rows)| {
+            let pixels = rows.iter().flat_map(|row| (0..5).map(move |i| row & (1 << (4 - i)) != 0)).collect();
+            let glyph = BitmapGlyph { width: 5, left: 0, top: -7, advance: 6, pixels };
+            (char::from(0x20 + idx as u8), glyph)
+        }).collect();
+
+        Self { glyphs, ascent: 7, descent: 1 }
+    }
+
+    /// Loads a PSF (Linux console) or BDF (X11) font from a file, going by its contents.
+    pub fn load(path: PathBuf) -> Result<Self, Box<dyn Error>> {
+        let data = fs::read(path)?;
+        if data.starts_with(&PSF1_MAGIC) || data.starts_with(&PSF2_MAGIC) {
+            Self::parse_psf(&data)
+        } else {
+            Self::parse_bdf(std::str::from_utf8(&data)?)
+        }
+    }
+
+    /// Parses a version 1 or 2 PSF font, like the ones in `/usr/share/consolefonts` once
+    /// decompressed. Fonts without a unicode table are assumed to be in Latin-1 order.
+    pub fn parse_psf(data: &[u8]) -> Result<Self, Box<dyn Error>> {
+        let read_u32 = |off: usize| -> Result<usize, Box<dyn Error>> {
+            let bytes = data.get(off..off + 4).ok_or("PSF header is truncated")?;
+            Ok(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]) as usize)
+        };
+
+        let (header_size, count, char_size, height, width, table) = if data.starts_with(&PSF1_MAGIC) {
+            let (mode, char_size) = (*data.get(2).ok_or("PSF header is truncated")?, *data.get(3).ok_or("PSF header is truncated")?);
+            let count = if mode & 0x01 != 0 { 512 } else { 256 };
+            (4, count, char_size as usize, char_size as usize, 8, mode & 0x06 != 0)
+        } else if data.starts_with(&PSF2_MAGIC) {
+            (read_u32(8)?, read_u32(16)?, read_u32(20)?, read_u32(24)?, read_u32(28)?, read_u32(12)? & 0x01 != 0)
+        } else {
+            return Err("not a PSF font".into());
+        };
+
+        // Sizes come from the file, so they may be nonsense that overflows.
+        let glyphs_end = count.checked_mul(char_size).and_then(|size| size.checked_add(header_size));
+        let glyph_data = glyphs_end.and_then(|end| data.get(header_size..end)).ok_or("PSF glyphs are truncated")?;
+        if width > MAX_GLYPH_SIZE as usize || height > MAX_GLYPH_SIZE as usize {
+            return Err("PSF glyphs are too big".into());
+        }
+        if char_size == 0 || width.checked_add(7).and_then(|w| (w / 8).checked_mul(height)).is_none_or(|size| size > char_size) {
+            return Err("PSF glyph size doesn't match its width and height".into());
+        }
+        let chars = if table {
+            Self::psf_unicode_table(&data[header_size + glyph_data.len()..], count, data.starts_with(&PSF1_MAGIC))
+        } else {
+            (0..count as u32).map(|c| char::from_u32(c).into_iter().collect()).collect()
+        };
+
+        // PSF fonts don't record a baseline, so a quarter of the height is assumed to be below it.
+        let descent = height / 4;
+        let ascent = height - descent;
+        let mut glyphs = HashMap::new();
+        for (bitmap, chars) in glyph_data.chunks(char_size).zip(chars) {
+            for c in chars {
+                glyphs.insert(c, BitmapGlyph {
+                    width,
+                    left: 0,
+                    top: -(ascent as i32),
+                    advance: width,
+                    pixels: unpack_rows(bitmap, width, height),
+                });
+            }
+        }
+
+        Ok(Self { glyphs, ascent, descent })
+    }
+
+    /// Reads which characters each glyph of a PSF font draws. Sequences of combining characters are
+    /// skipped, as they can't be looked up one character at a time.
+    fn psf_unicode_table(mut table: &[u8], count: usize, psf1: bool) -> Vec<Vec<char>> {
+        let mut chars = vec![Vec::new(); count];
+        for glyph_chars in chars.iter_mut() {
+            if psf1 {
+                // UCS-2 values, ending at 0xFFFF with sequences after 0xFFFE.
+                let mut in_sequence = false;
+                while table.len() >= 2 {
+                    let value = u16::from_le_bytes([table[0], table[1]]);
+                    table = &table[2..];
+                    match value {
+                        0xFFFF => break,
+                        0xFFFE => in_sequence = true,
+                        _ if !in_sequence => glyph_chars.extend(char::from_u32(value as u32)),
+                        _ => {}
+                    }
+                }
+            } else {
+                // UTF-8, ending at 0xFF with sequences after 0xFE.
+                let end = table.iter().position(|&b| b == 0xFF).unwrap_or(table.len());
+                let singles = table[..end].split(|&b| b == 0xFE).next().unwrap_or_default();
+                glyph_chars.extend(String::from_utf8_lossy(singles).chars());
+                table = table.get(end + 1..).unwrap_or_default();
+            }
+        }
+        chars
+    }
+
+    /// Parses a BDF font. Glyphs are looked up by their `ENCODING`, which is Unicode for
+    /// `ISO10646-1` fonts and close enough for `ISO8859-1` ones.
+    pub fn parse_bdf(text: &str) -> Result<Self, Box<dyn Error>> {
+        let mut glyphs = HashMap::new();
+        let (mut ascent, mut descent) = (None, None);
+        let mut bounding_box = (0, 0);
+
+        let mut lines = text.lines().enumerate();
+        while let Some((num, line)) = lines.next() {
+            let mut words = line.split_whitespace();
+            let number = |value: Option<&str>| -> Result<i32, Box<dyn Error>> {
+                value.ok_or_else(|| format!("line {}: missing number", num + 1))?
+                    .parse()
+                    .map_err(|_| format!("line {}: expected a number", num + 1).into())
+            };
+
+            match words.next() {
+                Some("FONT_ASCENT") => ascent = Some(number(words.next())?),
+                Some("FONT_DESCENT") => descent = Some(number(words.next())?),
+                Some("FONTBOUNDINGBOX") => {
+                    let height = number(words.nth(1))?;
+                    let y = number(words.nth(1))?;
+                    if !in_range(height) || !in_range(y) {
+                        return Err(format!("line {}: font bounding box out of range", num + 1).into());
+                    }
+                    bounding_box = (height + y, -y);
+                }
+                Some("STARTCHAR") => {
+                    let (mut encoding, mut advance, mut bbx) = (-1, 0, (0, 0, 0, 0));
+                    let mut rows = Vec::new();
+                    let mut in_bitmap = false;
+
+                    for (num, line) in lines.by_ref() {
+                        let mut words = line.split_whitespace();
+                        let keyword = words.next();
+                        let mut number = || -> Result<i32, Box<dyn Error>> {
+                            words.next().and_then(|w| w.parse().ok()).ok_or_else(|| format!("line {}: expected a number", num + 1).into())
+                        };
+                        match keyword {
+                            Some("ENDCHAR") => break,
+                            Some("ENCODING") => encoding = number()?,
+                            Some("DWIDTH") => {
+                                advance = number()?;
+                                if !in_range(advance) {
+                                    return Err(format!("line {}: advance out of range", num + 1).into());
+                                }
+                            }
+                            Some("BBX") => {
+                                bbx = (number()?, number()?, number()?, number()?);
+                                let (width, height, x, y) = bbx;
+                                if width < 0 || height < 0 || ![width, height, x, y].into_iter().all(in_range) {
+                                    return Err(format!("line {}: glyph bounding box out of range", num + 1).into());
+                                }
+                            }
+                            Some("BITMAP") => in_bitmap = true,
+                            Some(hex) if in_bitmap => {
+                                for pair in hex.as_bytes().chunks(2) {
+                                    let pair = std::str::from_utf8(pair)?;
+                                    rows.push(u8::from_str_radix(pair, 16).map_err(|_| format!("line {}: bad bitmap row", num + 1))?);
+                                }
+                            }
+                            _ => {}
+                        }
+                    }
+
+                    let (width, height, x, y) = bbx;
+                    let c = match u32::try_from(encoding).ok().and_then(char::from_u32) {
+                        Some(c) => c,
+                        None => continue,
+                    };
+                    glyphs.insert(c, BitmapGlyph {
+                        width: width as usize,
+                        left: x,
+                        top: -(y + height),
+                        advance: advance.max(0) as usize,
+                        pixels: unpack_rows(&rows, width as usize, height as usize),
+                    });
+                }
+                _ => {}
+            }
+        }
+
+        if glyphs.is_empty() {
+            return Err("no glyphs in BDF font".into());
+        }
+        Ok(Self {
+            glyphs,
+            ascent: ascent.unwrap_or(bounding_box.0).max(0) as usize,
+            descent: descent.unwrap_or(bounding_box.1).max(0) as usize,
+        })
+    }
+
+    /// Returns the height of a line of text in pixels, before scaling.
+    pub fn line_height(&self) -> usize {
+        self.ascent + self.descent
+    }
+
+    /// Returns the glyph for a character, or a stand-in if the font doesn't have it.
+    fn glyph(&self, c: char) -> Option<&BitmapGlyph> {
+        self.glyphs.get(&c)
+            .or_else(|| self.glyphs.get(&char::REPLACEMENT_CHARACTER))
+            .or_else(|| self.glyphs.get(&'?'))
+    }
+
+    /// Returns the whole multiple the font is scaled by to be closest to `height` pixels tall.
+    fn scale_for(&self, height: f32) -> usize {
+        (height / self.line_height().max(1) as f32).round().max(1.0) as usize
+    }
+
+    /// Returns the width of a line of text drawn at `scale`.
+    fn measure(&self, text: &str, scale: usize) -> usize {
+        text.chars()
+            .filter(|c| !c.is_control())
+            .filter_map(|c| self.glyph(c))
+            .map(|g| g.advance * scale)
+            .sum()
+    }
+
+    /// Returns the box covered by the set pixels of a line of text drawn at `scale`, with the pen
+    /// starting at (0, baseline).
+    fn ink(&self, text: &str, scale: usize, baseline: i32) -> Option<InkBounds> {
+        let mut pen = 0;
+        let mut ink: Option<InkBounds> = None;
+
+        for glyph in text.chars().filter(|c| !c.is_control()).filter_map(|c| self.glyph(c)) {
+            let set = glyph.pixels.iter().enumerate().filter(|(_, &set)| set).map(|(idx, _)| idx);
+            let (cols, rows): (Vec<_>, Vec<_>) = set.map(|idx| (idx % glyph.width, idx / glyph.width)).unzip();
+            if let (Some(&l), Some(&r), Some(&t), Some(&b)) = (cols.iter().min(), cols.iter().max(), rows.iter().min(), rows.iter().max()) {
+                let left = pen + glyph.left * scale as i32;
+                let top = baseline + glyph.top * scale as i32;
+                let bounds = InkBounds {
+                    left: (left + (l * scale) as i32) as f32,
+                    top: (top + (t * scale) as i32) as f32,
+                    right: (left + ((r + 1) * scale) as i32) as f32,
+                    bottom: (top + ((b + 1) * scale) as i32) as f32,
+                };
+                ink = Some(ink.map_or(bounds, |ink| ink.union(bounds)));
+            }
+            pen += (glyph.advance * scale) as i32;
+        }
+
+        ink
+    }
+
+    /// Draws a line of text into a coverage mask with the pen starting at (x, baseline).
+    fn draw(&self, text: &str, scale: usize, x: usize, baseline: i32, coverage: &mut [u8], width: usize) {
+        let height = coverage.len() / width.max(1);
+        let mut pen = x as i32;
+
+        for glyph in text.chars().filter(|c| !c.is_control()).filter_map(|c| self.glyph(c)) {
+            let left = pen + glyph.left * scale as i32;
+            let top = baseline + glyph.top * scale as i32;
+            for (idx, _) in glyph.pixels.iter().enumerate().filter(|(_, &set)| set) {
+                let gx = left + ((idx % glyph.width) * scale) as i32;
+                let gy = top + ((idx / glyph.width) * scale) as i32;
+                for (sx, sy) in (0..scale as i32).flat_map(|sy| (0..scale as i32).map(move |sx| (sx, sy))) {
+                    let (px, py) = (gx + sx, gy + sy);
+                    if px >= 0 && (px as usize) < width && py >= 0 && (py as usize) < height {
+                        coverage[py as usize * width + px as usize] = u8::MAX;
+                    }
+                }
+            }
+            pen += (glyph.advance * scale) as i32;
+        }
+    }
+}
+
+/// Unpacks glyph rows that are padded to whole bytes, leftmost pixel in the highest bit.
+fn unpack_rows(data: &[u8], width: usize, height: usize) -> Vec<bool> {
+    let stride = width.div_ceil(8);
+    (0..height)
+        .flat_map(|y| (0..width).map(move |x| (y, x)))
+        .map(|(y, x)| data.get(y * stride + x / 8).is_some_and(|byte| byte & (0x80 >> (x % 8)) != 0))
+        .collect()
+}
+
+/// Renders text with bitmap fonts. Has the same rendering API as `TextRenderer`, for apps built
+/// without `rusttype`. Sizes are rounded to the nearest whole multiple of the font's own size, so
+/// the pixels stay crisp.
+#[derive(Default)]
+pub struct BitmapTextRenderer {
+    fonts: Vec<(String, BitmapFont)>,
+}
+
+impl BitmapTextRenderer {
+    /// Loads a font with a given name, replacing any font with the same name.
+    pub fn load_font(&mut self, named: &str, font: BitmapFont) {
+        self.unload_font(named);
+        self.fonts.push((named.to_owned(), font));
+    }
+
+    /// Unloads a font. Returns false if there is no such font.
+    pub fn unload_font(&mut self, named: &str) -> bool {
+        let count = self.fonts.len();
+        self.fonts.retain(|(name, _)| name != named);
+        self.fonts.len() != count
+    }
+
+    /// Returns a font with a given name. Falls back to the first font loaded if there's no such
+    /// font, such as one that was unloaded, and to the built-in font if none are loaded.
+    fn find_font(&self, named: &str) -> &BitmapFont {
+        static BUILTIN: OnceLock<BitmapFont> = OnceLock::new();
+
+        self.fonts.iter()
+            .find(|(name, _)| name == named)
+            .or(self.fonts.first())
+            .map(|(_, font)| font)
+            .unwrap_or_else(|| BUILTIN.get_or_init(BitmapFont::builtin))
+    }
+
+    pub fn render(&self, text: &str, font: &str, height: f32, color: &impl Colorful) -> Text {
+        let font = self.find_font(font);
+        let scale = font.scale_for(height);
+
+        let width = font.measure(text, scale);
+        let mut coverage = vec![0u8; width * font.line_height() * scale];
+        font.draw(text, scale, 0, (font.ascent * scale) as i32, &mut coverage, width);
+
+        Text::new(text, coverage, None, width, color)
+    }
+
+    /// Measures a line of text as `render` would draw it.
+    pub fn measure(&self, text: &str, font: &str, height: f32) -> TextMetrics {
+        let font = self.find_font(font);
+        let scale = font.scale_for(height);
+        let ascent = font.ascent * scale;
+
+        TextMetrics {
+            advance: font.measure(text, scale) as f32,
+            ink: font.ink(text, scale, ascent as i32),
+            ascent: ascent as f32,
+            descent: (font.descent * scale) as f32,
+            line_gap: 0.0,
+            baseline: ascent as f32,
+        }
+    }
+
+    /// Lays out multiple lines of text within a box and renders them, like
+    /// `TextRenderer::render_paragraph`.
+    pub fn render_paragraph(&self, text: &str, font: &str, height: f32, color: &impl Colorful, style: &ParagraphStyle) -> Paragraph {
+        let font = self.find_font(font);
+        let scale = font.scale_for(height);
+        let measure = |s: &str| font.measure(s, scale) as f32;
+
+        let ascent = (font.ascent * scale) as f32;
+        let line_height = (font.line_height() * scale) as f32;
+        let (width, bitmap_height, lines) = layout::place_paragraph(text, style, ascent, line_height, measure);
+
+        let mut coverage = vec![0u8; width * bitmap_height];
+        let mut metrics = Vec::with_capacity(lines.len());
+        for line in lines {
+            for (word, x) in line.words {
+                font.draw(&word, scale, x.round() as usize, line.metrics.baseline.round() as i32, &mut coverage, width);
+            }
+            metrics.push(line.metrics);
+        }
+
+        Paragraph {
+            text: Text::new(text, coverage, None, width, color),
+            height: bitmap_height,
+            lines: metrics,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The pixels of a glyph as rows of `#` and `.`.
+    fn rows(font: &BitmapFont, c: char) -> Vec<String> {
+        let glyph = &font.glyphs[&c];
+        glyph.pixels.chunks(glyph.width).map(|row| row.iter().map(|&on| if on { '#' } else { '.' }).collect()).collect()
+    }
+
+    /// A PSF2 font of 8x2 glyphs, with a header that can be tampered with.
+    fn psf2(count: u32, flags: u32, glyphs: &[u8], table: &[u8]) -> Vec<u8> {
+        let header = [32, flags, count, 2, 2, 8];
+        PSF2_MAGIC.iter().copied()
+            .chain(0u32.to_le_bytes())
+            .chain(header.iter().flat_map(|value| value.to_le_bytes()))
+            .chain(glyphs.iter().copied())
+            .chain(table.iter().copied())
+            .collect()
+    }
+
+    #[test]
+    fn parses_psf1() {
+        // 256 glyphs two rows high, with a unicode table that gives the second glyph two characters.
+        let mut data = vec![0x36, 0x04, 0x02, 2, 0xff, 0x00, 0x81, 0x42];
+        data.extend([0; 254 * 2]);
+        data.extend([0x41, 0x00, 0xff, 0xff, 0x42, 0x00, 0x62, 0x00, 0xfe, 0xff, 0x43, 0x00, 0xff, 0xff]);
+        data.extend([0xff; 254 * 2]);
+        let font = BitmapFont::parse_psf(&data).unwrap();
+        assert_eq!(rows(&font, 'A'), ["########", "........"]);
+        assert_eq!(rows(&font, 'b'), ["#......#", ".#....#."]);
+        assert!(font.glyphs.contains_key(&'B'));
+        // Characters after 0xFFFE make up a sequence, which can't be looked up.
+        assert!(!font.glyphs.contains_key(&'C'));
+        assert_eq!((font.ascent, font.descent), (2, 0));
+    }
+
+    #[test]
+    fn parses_psf2() {
+        let font = BitmapFont::parse_psf(&psf2(2, 1, &[0xf0, 0x0f, 0x80, 0x01], b"x\xffy\xfez\xff")).unwrap();
+        assert_eq!(rows(&font, 'x'), ["####....", "....####"]);
+        assert_eq!(rows(&font, 'y'), ["#.......", ".......#"]);
+        assert!(!font.glyphs.contains_key(&'z'));
+
+        // Without a table, glyphs are in Latin-1 order.
+        let font = BitmapFont::parse_psf(&psf2(2, 0, &[0xf0, 0x0f, 0x80, 0x01], &[])).unwrap();
+        assert_eq!(rows(&font, '\u{1}'), ["#.......", ".......#"]);
+    }
+
+    #[test]
+    fn rejects_malformed_psf() {
+        assert!(BitmapFont::parse_psf(&[]).is_err());
+        assert!(BitmapFont::parse_psf(&[0x36, 0x04, 0x00]).is_err());
+        assert!(BitmapFont::parse_psf(&PSF2_MAGIC).is_err());
+        assert!(BitmapFont::parse_psf(&psf2(3, 0, &[0; 4], &[])).is_err());
+
+        // Sizes that overflow when multiplied or added.
+        let mut data = psf2(u32::MAX, 0, &[0; 4], &[]);
+        data[20..24].copy_from_slice(&u32::MAX.to_le_bytes());
+        assert!(BitmapFont::parse_psf(&data).is_err());
+        let mut data = psf2(1, 0, &[0; 4], &[]);
+        data[8..12].copy_from_slice(&u32::MAX.to_le_bytes());
+        assert!(BitmapFont::parse_psf(&data).is_err());
+
+        // Glyphs too big to be real.
+        let mut data = psf2(1, 0, &[0; 4], &[]);
+        data[24..28].copy_from_slice(&u32::MAX.to_le_bytes());
+        data[28..32].copy_from_slice(&0u32.to_le_bytes());
+        assert!(BitmapFont::parse_psf(&data).is_err());
+
+        // Glyphs that are too small for their width and height, or empty.
+        let mut data = psf2(1, 0, &[0; 4], &[]);
+        data[24..28].copy_from_slice(&100u32.to_le_bytes());
+        assert!(BitmapFont::parse_psf(&data).is_err());
+        let mut data = psf2(1, 0, &[0; 4], &[]);
+        data[20..24].copy_from_slice(&0u32.to_le_bytes());
+        assert!(BitmapFont::parse_psf(&data).is_err());
+    }
+
+    #[test]
+    fn parses_bdf() {
+        let font = BitmapFont::parse_bdf("\
+STARTFONT 2.1
+FONTBOUNDINGBOX 6 8 0 -2
+STARTCHAR A
+ENCODING 65
+DWIDTH 6 0
+BBX 4 3 1 -1
+BITMAP
+60
+90
+F0
+ENDCHAR
+STARTCHAR unencoded
+ENCODING -1
+BBX 1 1 0 0
+BITMAP
+80
+ENDCHAR
+ENDFONT
+").unwrap();
+        assert_eq!(rows(&font, 'A'), [".##.", "#..#", "####"]);
+        let glyph = &font.glyphs[&'A'];
+        assert_eq!((glyph.left, glyph.top, glyph.advance), (1, -2, 6));
+        assert_eq!(font.glyphs.len(), 1);
+        // Without FONT_ASCENT and FONT_DESCENT, the bounding box is used.
+        assert_eq!((font.ascent, font.descent), (6, 2));
+    }
+
+    #[test]
+    fn rejects_malformed_bdf() {
+        assert!(BitmapFont::parse_bdf("").is_err());
+        let error = |text: &str| BitmapFont::parse_bdf(text).err().unwrap().to_string();
+        // Huge glyphs, and offsets that would overflow.
+        assert!(error("STARTCHAR A\nENCODING 65\nBBX 100000 100000 0 0\nBITMAP\nENDCHAR\n").contains("line 3"));
+        assert!(error("STARTCHAR A\nENCODING 65\nBBX 8 2147483647 0 2147483647\nENDCHAR\n").contains("line 3"));
+        assert!(error("STARTCHAR A\nBBX -8 1 0 0\nENDCHAR\n").contains("line 2"));
+        assert!(error("STARTCHAR A\nDWIDTH 2147483647 0\nENDCHAR\n").contains("line 2"));
+        assert!(error("FONTBOUNDINGBOX 8 2147483647 0 2147483647\n").contains("line 1"));
+        assert!(BitmapFont::parse_bdf("FONT_ASCENT x\n").err().unwrap().to_string().contains("line 1"));
+        assert!(BitmapFont::parse_bdf("STARTCHAR A\nENCODING 65\nBBX 8 1 0 0\nBITMAP\nZZ\nENDCHAR\n").err().unwrap().to_string().contains("line 5"));
+    }
+
+    #[test]
+    fn missing_fonts_fall_back() {
+        let mut renderer = BitmapTextRenderer::default();
+        let builtin_width = BitmapFont::builtin().measure("Hi", 1);
+        assert_eq!(renderer.render("Hi", "missing", 7.0, &[255u8; 3]).width, builtin_width);
+
+        let mut wide = BitmapFont::builtin();
+        wide.glyphs.values_mut().for_each(|glyph| glyph.advance += 1);
+        renderer.load_font("wide", wide);
+        renderer.load_font("body", BitmapFont::builtin());
+        renderer.unload_font("body");
+        assert_eq!(renderer.render("Hi", "body", 7.0, &[255u8; 3]).width, builtin_width + 2);
+    }
+}
@@ -0,0 +1,218 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use rusttype::{point, PositionedGlyph, Scale};
+use crate::gfx::text::antialias::{self, Rendering, SubpixelOrder};
+use crate::gfx::text::emoji;
+
+/// Glyphs are rasterized at this many fractional offsets per pixel in each direction. Four is
+/// enough that spacing looks even, while keeping the number of variants of each glyph small.
+const SUBPIXEL_BINS: f32 = 4.0;
+
+/// The cache is emptied when it grows past this many glyphs. Anything still in use is rasterized
+/// again on the next render.
+const MAX_GLYPHS: usize = 2048;
+
+/// Everything that changes how a glyph rasterizes.
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+struct GlyphKey {
+    /// Id of the font in the `TextRenderer`.
+    font: usize,
+    /// Bits of the horizontal and vertical scale, since `f32` can't be hashed.
+    scale: (u32, u32),
+    glyph: u16,
+    /// Fractional position of the glyph, in `SUBPIXEL_BINS`.
+    offset: (u8, u8),
+}
+
+/// The rasterized coverage of one glyph, and its colors if it is a color emoji.
+pub(crate) struct GlyphMask {
+    /// Offset of the top left of the mask from the glyph's whole pixel position.
+    pub(crate) left: i32,
+    pub(crate) top: i32,
+    pub(crate) width: usize,
+    /// One byte of coverage per pixel, 0 (untouched) to 255 (fully covered).
+    pub(crate) coverage: Vec<u8>,
+    /// RGBA pixels for glyphs that bring their own colors. These are drawn as-is instead of in the
+    /// text color.
+    pub(crate) rgba: Option<Vec<u8>>,
+    /// Red, green and blue coverage of each pixel, for LCD subpixel rendering.
+    pub(crate) lcd: Option<Vec<u8>>,
+}
+
+/// Remembers rasterized glyphs so text that changes every frame, like scores and clocks, only pays
+/// for rasterizing each glyph the first time it is drawn.
+#[derive(Default)]
+pub struct GlyphCache {
+    glyphs: Mutex<HashMap<GlyphKey, Arc<GlyphMask>>>,
+}
+
+impl GlyphCache {
+    /// Returns the coverage mask for a positioned glyph, along with the whole pixel position the
+    /// mask's offsets are relative to. `color_font` is the font file of fonts with color glyphs.
+    /// The cache must be cleared when `rendering` changes.
+    pub(crate) fn get(&self, font: usize, glyph: &PositionedGlyph, color_font: Option<&[u8]>, rendering: &Rendering) -> (Arc<GlyphMask>, i32, i32) {
+        let position = glyph.position();
+        let (x, mut bin_x) = Self::split_position(position.x);
+        let (y, mut bin_y) = Self::split_position(position.y);
+        // Bitmaps can't be shifted by a fraction of a pixel, so there's only one variant of them.
+        if color_font.is_some() || !rendering.subpixel_positioning {
+            (bin_x, bin_y) = (0, 0);
+        }
+        let scale = glyph.scale();
+
+        let key = GlyphKey {
+            font,
+            scale: (scale.x.to_bits(), scale.y.to_bits()),
+            glyph: glyph.id().0,
+            offset: (bin_x, bin_y),
+        };
+
+        let mut glyphs = self.glyphs.lock().unwrap();
+        if let Some(mask) = glyphs.get(&key) {
+            return (mask.clone(), x, y);
+        }
+
+        if glyphs.len() >= MAX_GLYPHS {
+            glyphs.clear();
+        }
+
+        let color_mask = color_font.and_then(|data| emoji::rasterize(data, glyph.font(), glyph.id(), glyph.scale()));
+        let mask = Arc::new(color_mask.unwrap_or_else(|| {
+            let offset = point(bin_x as f32 / SUBPIXEL_BINS, bin_y as f32 / SUBPIXEL_BINS);
+            let glyph = glyph.unpositioned().clone().positioned(offset);
+            match rendering.lcd {
+                Some(order) => Self::rasterize_lcd(&glyph, order, rendering),
+                None => Self::rasterize(&glyph, rendering),
+            }
+        }));
+        glyphs.insert(key, mask.clone());
+        (mask, x, y)
+    }
+
+    /// Forgets the cached glyphs of one font, such as one that was unloaded.
+    pub(crate) fn remove_font(&self, font: usize) {
+        self.glyphs.lock().unwrap().retain(|key, _| key.font != font);
+    }
+
+    /// Forgets every cached glyph.
+    pub fn clear(&self) {
+        self.glyphs.lock().unwrap().clear();
+    }
+
+    /// Returns how many glyphs are cached.
+    pub fn len(&self) -> usize {
+        self.glyphs.lock().unwrap().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Splits a coordinate into whole pixels and the nearest subpixel bin.
+    fn split_position(v: f32) -> (i32, u8) {
+        let whole = v.floor();
+        let bin = ((v - whole) * SUBPIXEL_BINS).round();
+        if bin >= SUBPIXEL_BINS {
+            (whole as i32 + 1, 0)
+        } else {
+            (whole as i32, bin as u8)
+        }
+    }
+
+    fn rasterize(glyph: &PositionedGlyph, rendering: &Rendering) -> GlyphMask {
+        let bb = match glyph.pixel_bounding_box() {
+            Some(bb) => bb,
+            // Spaces and other blank glyphs.
+            None => return GlyphMask { left: 0, top: 0, width: 0, coverage: Vec::new(), rgba: None, lcd: None },
+        };
+
+        // Leave room for stem darkening to grow the glyph.
+        let pad = (rendering.stem_darkening > 0.0) as i32;
+        let (width, height) = ((bb.width() + pad * 2) as usize, (bb.height() + pad * 2) as usize);
+        let mut coverage = vec![0u8; width * height];
+        glyph.draw(|x, y, v| {
+            coverage[(y as usize + pad as usize) * width + x as usize + pad as usize] = (v * u8::MAX as f32) as u8;
+        });
+        rendering.adjust(&mut coverage, width);
+
+        GlyphMask { left: bb.min.x - pad, top: bb.min.y - pad, width, coverage, rgba: None, lcd: None }
+    }
+
+    /// Rasterizes a glyph at three times the horizontal resolution, then filters it and gives each
+    /// subpixel of a pixel its own coverage.
+    fn rasterize_lcd(glyph: &PositionedGlyph, order: SubpixelOrder, rendering: &Rendering) -> GlyphMask {
+        let scale = glyph.scale();
+        let position = glyph.position();
+        let wide = glyph.unpositioned().unscaled().clone()
+            .scaled(Scale { x: scale.x * 3.0, y: scale.y })
+            .positioned(point(position.x * 3.0, position.y));
+        let bb = match wide.pixel_bounding_box() {
+            Some(bb) => bb,
+            None => return GlyphMask { left: 0, top: 0, width: 0, coverage: Vec::new(), rgba: None, lcd: None },
+        };
+
+        // The filter spreads coverage two subpixels either way, and whole pixels start at
+        // multiples of three subpixels.
+        // Stem darkening grows the glyph by a subpixel sideways and a pixel up and down.
+        let pad = (rendering.stem_darkening > 0.0) as i32;
+        let left = (bb.min.x - 2 - pad).div_euclid(3);
+        let sub_width = ((bb.max.x + 2 + pad - left * 3) as usize).div_ceil(3) * 3;
+        let height = (bb.height() + pad * 2) as usize;
+        let shift = (bb.min.x - left * 3) as usize;
+
+        let mut subpixels = vec![0u8; sub_width * height];
+        wide.draw(|x, y, v| {
+            subpixels[(y as usize + pad as usize) * sub_width + x as usize + shift] = (v * u8::MAX as f32) as u8;
+        });
+        rendering.apply_darkening(&mut subpixels, sub_width);
+        for row in subpixels.chunks_mut(sub_width) {
+            antialias::lcd_filter(row);
+        }
+        rendering.apply_gamma(&mut subpixels);
+
+        let mut lcd = Vec::with_capacity(subpixels.len());
+        for px in subpixels.chunks(3) {
+            match order {
+                SubpixelOrder::Rgb => lcd.extend_from_slice(px),
+                SubpixelOrder::Bgr => lcd.extend(px.iter().rev()),
+            }
+        }
+        // Rounded up, so pixels where only one subpixel is lit aren't skipped as blank.
+        let coverage = lcd.chunks(3).map(|px| (px[0] as u16 + px[1] as u16 + px[2] as u16).div_ceil(3) as u8).collect();
+
+        GlyphMask { left, top: bb.min.y - pad, width: sub_width / 3, coverage, rgba: None, lcd: Some(lcd) }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rusttype::Font;
+
+    fn glyph(c: char) -> PositionedGlyph<'static> {
+        let font = Font::try_from_bytes(include_bytes!("../../OpenSans-CondLight.ttf")).unwrap();
+        font.glyph(c).scaled(Scale::uniform(24.0)).positioned(point(0.25, 0.0))
+    }
+
+    #[test]
+    fn lcd_glyphs_are_darkened() {
+        let plain = Rendering { lcd: Some(SubpixelOrder::Rgb), ..Default::default() };
+        let darkened = Rendering { stem_darkening: 0.5, ..plain };
+        let sum = |mask: &GlyphMask| mask.lcd.as_ref().unwrap().iter().map(|&v| v as u32).sum::<u32>();
+
+        let glyph = glyph('l');
+        let plain = GlyphCache::rasterize_lcd(&glyph, SubpixelOrder::Rgb, &plain);
+        let darkened = GlyphCache::rasterize_lcd(&glyph, SubpixelOrder::Rgb, &darkened);
+        assert!(sum(&darkened) > sum(&plain));
+    }
+
+    #[test]
+    fn lcd_fringes_have_coverage() {
+        let rendering = Rendering { lcd: Some(SubpixelOrder::Bgr), ..Default::default() };
+        let mask = GlyphCache::rasterize_lcd(&glyph('i'), SubpixelOrder::Bgr, &rendering);
+        let lcd = mask.lcd.as_ref().unwrap();
+        for (px, &coverage) in lcd.chunks(3).zip(&mask.coverage) {
+            assert_eq!(coverage == 0, px == [0, 0, 0]);
+        }
+    }
+}
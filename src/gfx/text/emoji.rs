@@ -0,0 +1,46 @@
+use image::imageops::{resize, FilterType};
+use image::load_from_memory;
+use rusttype::{Font, GlyphId, Scale};
+use ttf_parser::{Face, RasterImageFormat};
+use crate::gfx::text::cache::GlyphMask;
+
+/// Returns whether a font has color bitmap glyphs, as color emoji fonts do.
+pub(crate) fn has_color_glyphs(data: &[u8]) -> bool {
+    Face::from_slice(data, 0)
+        .map(|face| face.tables().cbdt.is_some() || face.tables().sbix.is_some())
+        .unwrap_or(false)
+}
+
+/// Rasterizes a glyph from a CBDT (Noto Color Emoji) or sbix (Apple Color Emoji) font. The PNG
+/// strike closest to the requested size is decoded and scaled to fit. Returns `None` if the glyph
+/// has no bitmap, in which case the outline should be drawn instead.
+pub(crate) fn rasterize(data: &[u8], font: &Font, glyph: GlyphId, scale: Scale) -> Option<GlyphMask> {
+    let face = Face::from_slice(data, 0).ok()?;
+
+    // rusttype scales so that ascent to descent spans `scale.y` pixels, while strikes are chosen
+    // by pixels per em.
+    let v_metrics = font.v_metrics_unscaled();
+    let px_per_unit = scale.y / (v_metrics.ascent - v_metrics.descent);
+    let ppem = font.units_per_em() as f32 * px_per_unit;
+
+    let image = face.glyph_raster_image(ttf_parser::GlyphId(glyph.0), ppem.round() as u16)?;
+    if image.format != RasterImageFormat::PNG {
+        return None;
+    }
+
+    let decoded = load_from_memory(image.data).ok()?.to_rgba8();
+    let factor = ppem / image.pixels_per_em as f32;
+    let width = ((decoded.width() as f32 * factor).round() as u32).max(1);
+    let height = ((decoded.height() as f32 * factor).round() as u32).max(1);
+    let rgba = resize(&decoded, width, height, FilterType::Triangle).into_raw();
+
+    Some(GlyphMask {
+        left: (image.x as f32 * factor).round() as i32,
+        // `y` is the bottom of the image above the baseline, with Y pointing up.
+        top: -((image.y as f32 + image.height as f32) * factor).round() as i32,
+        width: width as usize,
+        coverage: rgba.chunks(4).map(|px| px[3]).collect(),
+        rgba: Some(rgba),
+        lcd: None,
+    })
+}
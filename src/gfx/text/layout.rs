@@ -0,0 +1,347 @@
+/// Horizontal placement of each line within the paragraph width.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum Align {
+    #[default]
+    Left,
+    Center,
+    Right,
+    /// Stretches the spaces between words so every line but the last of each paragraph fills the
+    /// width.
+    Justify,
+}
+
+/// Vertical placement of the lines within `ParagraphStyle::box_height`.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum VerticalAlign {
+    #[default]
+    Top,
+    Middle,
+    Bottom,
+}
+
+/// How to lay out a block of text.
+#[derive(Clone, Debug)]
+pub struct ParagraphStyle {
+    /// Lines are wrapped between words to fit this many pixels. `None` only breaks on `\n`.
+    pub max_width: Option<usize>,
+    /// Multiplier for the font's line height. 1.0 uses the spacing the font designer picked.
+    pub line_spacing: f32,
+    pub align: Align,
+    /// Text that needs more lines than this is cut off, ending with an ellipsis.
+    pub max_lines: Option<usize>,
+    /// Height of the box the text is placed in. `None` makes the bitmap as tall as the text.
+    pub box_height: Option<usize>,
+    pub vertical_align: VerticalAlign,
+}
+
+impl Default for ParagraphStyle {
+    fn default() -> Self {
+        Self {
+            max_width: None,
+            line_spacing: 1.0,
+            align: Align::Left,
+            max_lines: None,
+            box_height: None,
+            vertical_align: VerticalAlign::Top,
+        }
+    }
+}
+
+/// Where a line of a laid out paragraph ended up, in pixels from the top left of the bitmap.
+#[derive(Clone, Debug)]
+pub struct LineMetrics {
+    /// The text on this line, after wrapping and truncation.
+    pub text: String,
+    /// Left edge of the first glyph.
+    pub x: f32,
+    /// Top of the line box.
+    pub top: f32,
+    /// Baseline the glyphs sit on.
+    pub baseline: f32,
+    /// Width of the text on this line, including stretched spaces when justified.
+    pub width: f32,
+    /// Height of the line box, including line spacing.
+    pub height: f32,
+}
+
+/// The box a piece of text's glyphs actually cover, in pixels from the top left of its bitmap.
+#[derive(Clone, Copy, Debug, PartialEq, Default)]
+pub struct InkBounds {
+    pub left: f32,
+    pub top: f32,
+    pub right: f32,
+    pub bottom: f32,
+}
+
+impl InkBounds {
+    pub fn width(&self) -> f32 {
+        self.right - self.left
+    }
+
+    pub fn height(&self) -> f32 {
+        self.bottom - self.top
+    }
+
+    /// Grows the bounds to also cover `other`.
+    pub(crate) fn union(self, other: InkBounds) -> InkBounds {
+        InkBounds {
+            left: self.left.min(other.left),
+            top: self.top.min(other.top),
+            right: self.right.max(other.right),
+            bottom: self.bottom.max(other.bottom),
+        }
+    }
+}
+
+/// The size of a line of text, measured without rendering it. Handy for centering a label before
+/// drawing it. Positions are in pixels from the top left of the bitmap `render` would produce.
+#[derive(Clone, Copy, Debug, PartialEq, Default)]
+pub struct TextMetrics {
+    /// How far the text moves the pen, which is where text drawn after it would start. This is
+    /// also the width of the rendered bitmap, before rounding up.
+    pub advance: f32,
+    /// What the glyphs actually cover. This can start after the pen position or stick out past the
+    /// advance, depending on the glyphs. `None` if nothing is visible, such as for spaces.
+    pub ink: Option<InkBounds>,
+    /// How far the font reaches above the baseline.
+    pub ascent: f32,
+    /// How far the font reaches below the baseline, as a positive number.
+    pub descent: f32,
+    /// Extra space the font designer put between lines.
+    pub line_gap: f32,
+    /// Distance from the top of the bitmap to the baseline the glyphs sit on.
+    pub baseline: f32,
+}
+
+/// A line produced by wrapping, before it is positioned.
+pub(crate) struct WrappedLine {
+    pub(crate) text: String,
+    /// Whether this is the last line of a paragraph, which is never justified.
+    pub(crate) last_in_paragraph: bool,
+}
+
+/// Breaks `text` into lines no wider than `max_width`, as measured by `measure`. Lines break
+/// between words where possible, and inside words that are too long to fit on a line by
+/// themselves. `\n` always starts a new line.
+pub(crate) fn wrap(text: &str, max_width: Option<f32>, measure: impl Fn(&str) -> f32) -> Vec<WrappedLine> {
+    let mut lines = Vec::new();
+
+    for paragraph in text.split('\n') {
+        let max_width = match max_width {
+            Some(max_width) => max_width,
+            None => {
+                lines.push(WrappedLine { text: paragraph.to_owned(), last_in_paragraph: true });
+                continue;
+            }
+        };
+
+        let mut line = String::new();
+        for word in paragraph.split_whitespace() {
+            let candidate = if line.is_empty() { word.to_owned() } else { format!("{} {}", line, word) };
+            if measure(&candidate) <= max_width {
+                line = candidate;
+                continue;
+            }
+
+            if !line.is_empty() {
+                lines.push(WrappedLine { text: std::mem::take(&mut line), last_in_paragraph: false });
+            }
+
+            // The word doesn't fit on a line of its own, so break it wherever it overflows.
+            for c in word.chars() {
+                line.push(c);
+                if measure(&line) > max_width && line.chars().count() > 1 {
+                    line.pop();
+                    lines.push(WrappedLine { text: std::mem::take(&mut line), last_in_paragraph: false });
+                    line.push(c);
+                }
+            }
+        }
+        lines.push(WrappedLine { text: line, last_in_paragraph: true });
+    }
+
+    lines
+}
+
+/// Cuts `lines` down to `max_lines`, replacing the end of the last line with an ellipsis that
+/// still fits in `max_width`. If nothing fits, the line is just the ellipsis.
+pub(crate) fn truncate(lines: &mut Vec<WrappedLine>, max_lines: usize, max_width: Option<f32>, measure: impl Fn(&str) -> f32) {
+    const ELLIPSIS: char = '…';
+
+    if lines.len() <= max_lines {
+        return;
+    }
+    lines.truncate(max_lines);
+
+    let last = match lines.last_mut() {
+        Some(last) => last,
+        None => return,
+    };
+    last.last_in_paragraph = true;
+
+    let mut text = last.text.trim_end().to_owned();
+    loop {
+        let candidate = format!("{}{}", text, ELLIPSIS);
+        if text.is_empty() || max_width.is_none_or(|max_width| measure(&candidate) <= max_width) {
+            last.text = candidate;
+            return;
+        }
+        text.pop();
+        text.truncate(text.trim_end().len());
+    }
+}
+
+/// Splits a line into words with the x position of each word, relative to the left of the line.
+/// Words are spaced to fill `width` when justifying, and spaced naturally otherwise.
+pub(crate) fn place_words(line: &str, width: f32, justify: bool, measure: impl Fn(&str) -> f32) -> Vec<(String, f32)> {
+    let words: Vec<&str> = line.split(' ').filter(|w| !w.is_empty()).collect();
+    if !justify || words.len() < 2 {
+        return vec![(line.to_owned(), 0.0)];
+    }
+
+    let widths: Vec<f32> = words.iter().map(|w| measure(w)).collect();
+    let gap = (width - widths.iter().sum::<f32>()) / (words.len() - 1) as f32;
+
+    let mut x = 0.0;
+    words.iter().zip(widths).map(|(word, w)| {
+        let placed = (word.to_string(), x);
+        x += w + gap;
+        placed
+    }).collect()
+}
+
+/// A line of a paragraph ready to be drawn: where it goes, and each word with its x position.
+pub(crate) struct PlacedLine {
+    pub(crate) metrics: LineMetrics,
+    pub(crate) words: Vec<(String, f32)>,
+}
+
+/// Wraps, truncates and positions the lines of a paragraph for a font with the given `ascent` and
+/// `line_height`, measuring text with `measure`. Returns the width and height of the bitmap the
+/// paragraph needs along with the lines.
+pub(crate) fn place_paragraph(text: &str, style: &ParagraphStyle, ascent: f32, line_height: f32, measure: impl Fn(&str) -> f32) -> (usize, usize, Vec<PlacedLine>) {
+    // Break the text into lines.
+    let max_width = style.max_width.map(|w| w as f32);
+    let mut lines = wrap(text, max_width, &measure);
+    if let Some(max_lines) = style.max_lines {
+        truncate(&mut lines, max_lines, max_width, &measure);
+    }
+
+    // Size the bitmap. Without a max width, it is as wide as the longest line.
+    let natural_widths: Vec<f32> = lines.iter().map(|l| measure(&l.text)).collect();
+    let width = style.max_width
+        .unwrap_or_else(|| natural_widths.iter().cloned().fold(0.0, f32::max).ceil() as usize);
+    let line_height = line_height * style.line_spacing;
+    let text_height = line_height * lines.len() as f32;
+    let height = style.box_height.unwrap_or(text_height.ceil() as usize);
+
+    let top = match style.vertical_align {
+        VerticalAlign::Top => 0.0,
+        VerticalAlign::Middle => (height as f32 - text_height) / 2.0,
+        VerticalAlign::Bottom => height as f32 - text_height,
+    };
+
+    // Position every line and its words.
+    let placed = lines.into_iter().zip(natural_widths).enumerate().map(|(i, (line, natural_width))| {
+        let justify = style.align == Align::Justify && !line.last_in_paragraph && style.max_width.is_some();
+        let line_width = if justify { width as f32 } else { natural_width };
+        let x = match style.align {
+            Align::Left | Align::Justify => 0.0,
+            Align::Center => (width as f32 - line_width) / 2.0,
+            Align::Right => width as f32 - line_width,
+        };
+        let line_top = top + line_height * i as f32;
+
+        let words = place_words(&line.text, line_width, justify, &measure)
+            .into_iter()
+            .map(|(word, word_x)| (word, x + word_x))
+            .collect();
+        let metrics = LineMetrics { text: line.text, x, top: line_top, baseline: line_top + ascent, width: line_width, height: line_height };
+        PlacedLine { metrics, words }
+    }).collect();
+
+    (width, height, placed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Every character is one unit wide.
+    fn measure(text: &str) -> f32 {
+        text.chars().count() as f32
+    }
+
+    fn texts(lines: &[WrappedLine]) -> Vec<&str> {
+        lines.iter().map(|line| line.text.as_str()).collect()
+    }
+
+    #[test]
+    fn wraps_between_words() {
+        let lines = wrap("the quick brown fox\njumps", Some(10.0), measure);
+        assert_eq!(texts(&lines), ["the quick", "brown fox", "jumps"]);
+        assert_eq!(lines.iter().map(|line| line.last_in_paragraph).collect::<Vec<_>>(), [false, true, true]);
+        assert_eq!(texts(&wrap("the quick", None, measure)), ["the quick"]);
+    }
+
+    #[test]
+    fn breaks_over_long_words() {
+        assert_eq!(texts(&wrap("a abcdefgh b", Some(3.0), measure)), ["a", "abc", "def", "gh", "b"]);
+        // A line always gets at least one character, even if it doesn't fit.
+        assert_eq!(texts(&wrap("abc", Some(0.0), measure)), ["a", "b", "c"]);
+    }
+
+    #[test]
+    fn empty_text_is_one_empty_line() {
+        assert_eq!(texts(&wrap("", Some(10.0), measure)), [""]);
+        assert_eq!(texts(&wrap("", None, measure)), [""]);
+        assert_eq!(texts(&wrap("  ", Some(10.0), measure)), [""]);
+        assert_eq!(place_words("", 10.0, true, measure), [(String::new(), 0.0)]);
+
+        let (width, height, lines) = place_paragraph("", &ParagraphStyle::default(), 8.0, 10.0, measure);
+        assert_eq!((width, height, lines.len()), (0, 10, 1));
+    }
+
+    #[test]
+    fn truncates_with_an_ellipsis() {
+        let mut lines = wrap("one two three four", Some(7.0), measure);
+        truncate(&mut lines, 2, Some(7.0), measure);
+        assert_eq!(texts(&lines), ["one two", "three…"]);
+        assert!(lines[1].last_in_paragraph);
+
+        let mut lines = wrap("one two three four", Some(7.0), measure);
+        truncate(&mut lines, 5, Some(7.0), measure);
+        assert_eq!(texts(&lines), ["one two", "three", "four"]);
+
+        let mut lines = wrap("abcdefg hij", Some(7.0), measure);
+        truncate(&mut lines, 1, Some(7.0), measure);
+        assert_eq!(texts(&lines), ["abcdef…"]);
+    }
+
+    #[test]
+    fn ellipsis_at_zero_width() {
+        let mut lines = wrap("one two", Some(0.0), measure);
+        truncate(&mut lines, 1, Some(0.0), measure);
+        assert_eq!(texts(&lines), ["…"]);
+
+        let mut lines = wrap("one two", None, measure);
+        truncate(&mut lines, 0, Some(0.0), measure);
+        assert!(lines.is_empty());
+    }
+
+    #[test]
+    fn places_words() {
+        assert_eq!(place_words("a bb c", 6.0, false, measure), [("a bb c".to_owned(), 0.0)]);
+        assert_eq!(place_words("a bb c", 10.0, true, measure), [("a".to_owned(), 0.0), ("bb".to_owned(), 4.0), ("c".to_owned(), 9.0)]);
+        // One word can't be spread out.
+        assert_eq!(place_words("word", 10.0, true, measure), [("word".to_owned(), 0.0)]);
+    }
+
+    #[test]
+    fn justifies_all_but_the_last_line() {
+        let style = ParagraphStyle { max_width: Some(10), align: Align::Justify, ..Default::default() };
+        let (_, _, lines) = place_paragraph("aa bb cc dd", &style, 8.0, 10.0, measure);
+        assert_eq!(lines[0].words, [("aa".to_owned(), 0.0), ("bb".to_owned(), 4.0), ("cc".to_owned(), 8.0)]);
+        assert_eq!(lines[1].words, [("dd".to_owned(), 0.0)]);
+    }
+}
@@ -0,0 +1,168 @@
+use crate::gfx::color::Colorful;
+use crate::gfx::effects::{self, Shadow};
+use crate::gfx::text::layout::LineMetrics;
+use crate::Screen;
+
+pub mod bitmap;
+#[cfg(feature = "truetype")]
+pub mod antialias;
+#[cfg(feature = "truetype")]
+pub mod cache;
+#[cfg(feature = "truetype")]
+mod emoji;
+pub mod layout;
+pub mod rich;
+#[cfg(feature = "shaping")]
+mod shaping;
+#[cfg(feature = "truetype")]
+pub mod truetype;
+
+/// Draws one RGBA pixel over another, both with straight (not premultiplied) alpha.
+pub(crate) fn composite(dst: &mut [u8], src: &[u8]) {
+    let sa = src[3] as f32 / u8::MAX as f32;
+    let da = dst[3] as f32 / u8::MAX as f32;
+    let out_a = sa + da * (1.0 - sa);
+    if out_a <= 0.0 {
+        return;
+    }
+    for c in 0..3 {
+        dst[c] = ((src[c] as f32 * sa + dst[c] as f32 * da * (1.0 - sa)) / out_a) as u8;
+    }
+    dst[3] = (out_a * u8::MAX as f32) as u8;
+}
+
+pub struct BlittableText {
+    pub data: Vec<u8>,
+    pub width: usize,
+}
+
+pub struct Text {
+    text: String,
+    /// RGBA pixels, ready for `Screen::blend_image` or `Screen::draw_image_shadow`.
+    pub(crate) bitmap: Vec<u8>,
+    /// How much of each pixel the glyphs cover, for drawing in any color with `Screen::draw_text`.
+    pub(crate) coverage: Vec<u8>,
+    /// RGBA pixels that keep their own colors, such as emoji and rich text spans. These are drawn
+    /// as-is over the coverage.
+    pub(crate) color_glyphs: Option<Vec<u8>>,
+    /// Red, green and blue coverage of each pixel when rendered for an LCD. `Screen::draw_text`
+    /// uses it in place of `coverage`.
+    pub(crate) lcd: Option<Vec<u8>>,
+    pub width: usize,
+    /// Where the top left of the text as first rendered is in the bitmap. Outlines and shadows
+    /// grow the bitmap up and to the left.
+    offset: (usize, usize),
+}
+
+/// A block of text rendered by `TextRenderer::render_paragraph`.
+pub struct Paragraph {
+    /// The rendered bitmap, `text.width` by `height` pixels.
+    pub text: Text,
+    pub height: usize,
+    /// Where each line was placed in the bitmap.
+    pub lines: Vec<LineMetrics>,
+}
+
+impl Text {
+    /// Builds the RGBA bitmap by filling the coverage with `color`, then drawing color glyphs on top.
+    fn new(text: &str, coverage: Vec<u8>, color_glyphs: Option<Vec<u8>>, width: usize, color: &impl Colorful) -> Self {
+        let mut bitmap = vec![0u8; coverage.len() * 4];
+        for (idx, &v) in coverage.iter().enumerate() {
+            if v == 0 {
+                continue;
+            }
+            let (r, g, b, a) = color.as_rgba();
+            bitmap[idx * 4..idx * 4 + 4].copy_from_slice(&[r, g, b, (a as u16 * v as u16 / u8::MAX as u16) as u8]);
+        }
+        if let Some(layer) = &color_glyphs {
+            for (dst, src) in bitmap.chunks_mut(4).zip(layer.chunks(4)) {
+                composite(dst, src);
+            }
+        }
+
+        Self { text: text.to_owned(), bitmap, coverage, color_glyphs, lcd: None, width, offset: (0, 0) }
+    }
+
+    /// Returns the height of the bitmap in pixels.
+    pub fn height(&self) -> usize {
+        self.coverage.len().checked_div(self.width).unwrap_or(0)
+    }
+
+    /// Returns where the top left of the text as first rendered is in the bitmap, once outlines and
+    /// shadows have made it bigger. Draw at (x - offset.0, y - offset.1) to keep the glyphs in
+    /// place.
+    pub fn offset(&self) -> (usize, usize) {
+        self.offset
+    }
+
+    /// Draws an outline `width` pixels thick around the glyphs, behind them, to keep text readable
+    /// over busy photos. The bitmap grows by `width` on every side. Outlined text keeps its colors
+    /// when drawn with `Screen::draw_text`.
+    pub fn with_outline(self, width: usize, color: &impl Colorful) -> Text {
+        let (w, h) = (self.width + width * 2, self.height() + width * 2);
+        let mut mask = vec![0u8; w * h];
+        for (y, row) in effects::alpha_mask(&self.bitmap).chunks(self.width.max(1)).enumerate() {
+            let dst = (y + width) * w + width;
+            mask[dst..dst + row.len()].copy_from_slice(row);
+        }
+
+        let (r, g, b, a) = color.as_rgba();
+        let below = effects::dilate_round(&mask, w, h, width)
+            .into_iter()
+            .flat_map(|v| [r, g, b, (a as u16 * v as u16 / u8::MAX as u16) as u8])
+            .collect();
+        self.draw_over(below, w, (width, width))
+    }
+
+    /// Draws a shadow behind the text, such as `Shadow::drop` or `Shadow::glow`. The bitmap grows to
+    /// fit the shadow. Shadowed text keeps its colors when drawn with `Screen::draw_text`.
+    pub fn with_shadow(self, shadow: &Shadow) -> Text {
+        let height = self.height();
+        let (mask, sw, sh) = shadow.render_mask(&effects::alpha_mask(&self.bitmap), self.width, height);
+
+        // Fit both the text and the shadow, whose top left is relative to the text's.
+        let sx = shadow.offset.0 - shadow.margin() as i32;
+        let sy = shadow.offset.1 - shadow.margin() as i32;
+        let (left, top) = (sx.min(0), sy.min(0));
+        let w = ((sx + sw as i32).max(self.width as i32) - left) as usize;
+        let h = ((sy + sh as i32).max(height as i32) - top) as usize;
+
+        let (r, g, b, a) = shadow.color;
+        let mut below = vec![0u8; w * h * 4];
+        for (idx, &v) in mask.iter().enumerate() {
+            let x = (sx - left) as usize + idx % sw;
+            let y = (sy - top) as usize + idx / sw;
+            let off = (y * w + x) * 4;
+            below[off..off + 4].copy_from_slice(&[r, g, b, (a as u16 * v as u16 / u8::MAX as u16) as u8]);
+        }
+        self.draw_over(below, w, ((-left) as usize, (-top) as usize))
+    }
+
+    /// Draws this text's bitmap at `at` over an RGBA bitmap `width` pixels wide, and returns the
+    /// result as text whose colors are all fixed.
+    fn draw_over(self, mut below: Vec<u8>, width: usize, at: (usize, usize)) -> Text {
+        for (idx, px) in self.bitmap.chunks(4).enumerate() {
+            let off = ((at.1 + idx / self.width) * width + at.0 + idx % self.width) * 4;
+            composite(&mut below[off..off + 4], px);
+        }
+
+        Text {
+            text: self.text,
+            coverage: vec![0u8; below.len() / 4],
+            bitmap: below.clone(),
+            color_glyphs: Some(below),
+            lcd: None,
+            width,
+            offset: (self.offset.0 + at.0, self.offset.1 + at.1),
+        }
+    }
+
+    /// Prepares the texture for blitting onto the given screen, blended against a fixed background
+    /// color. Blitting is faster than `Screen::draw_text`, but only looks right on that background.
+    pub fn into_blittable(self, screen: &Screen, background: &impl Colorful) -> BlittableText {
+        BlittableText {
+            data: screen.render_image(&self.bitmap, background),
+            width: self.width,
+        }
+    }
+}
@@ -0,0 +1,256 @@
+use crate::gfx::color::{Color, Colorful};
+
+/// How one run of rich text looks.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SpanStyle {
+    /// Name the font was loaded with in the `TextRenderer`.
+    pub font: String,
+    /// Height of the text in pixels.
+    pub size: f32,
+    /// Color of the text and its decorations. Rich text keeps it even when drawn with a paint.
+    pub color: Color,
+    pub underline: bool,
+    pub strikethrough: bool,
+}
+
+impl SpanStyle {
+    pub fn new(font: &str, size: f32, color: &impl Colorful) -> Self {
+        Self { font: font.to_owned(), size, color: Color(color.as_rgba()), underline: false, strikethrough: false }
+    }
+
+    pub fn with_underline(mut self) -> Self {
+        self.underline = true;
+        self
+    }
+
+    pub fn with_strikethrough(mut self) -> Self {
+        self.strikethrough = true;
+        self
+    }
+}
+
+/// A run of text in a single style.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Span {
+    pub text: String,
+    pub style: SpanStyle,
+}
+
+/// Text made of runs that each have their own font, size, color and decorations, such as a
+/// sentence with one bold word. Render it with `TextRenderer::render_rich`.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct RichText {
+    pub spans: Vec<Span>,
+}
+
+impl RichText {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a run of text to the end.
+    pub fn push(&mut self, text: &str, style: &SpanStyle) {
+        self.spans.push(Span { text: text.to_owned(), style: style.clone() });
+    }
+
+    /// Adds a run of text to the end, for chaining.
+    pub fn with_span(mut self, text: &str, style: &SpanStyle) -> Self {
+        self.push(text, style);
+        self
+    }
+
+    /// Returns the text of every run, without styles.
+    pub fn plain_text(&self) -> String {
+        self.spans.iter().map(|s| s.text.as_str()).collect()
+    }
+}
+
+/// A word or run of spaces from one span, the smallest unit rich text is wrapped in.
+#[cfg(feature = "truetype")]
+pub(crate) struct Piece {
+    /// Index of the span in the `RichText`.
+    pub(crate) span: usize,
+    pub(crate) text: String,
+    pub(crate) width: f32,
+    pub(crate) space: bool,
+}
+
+/// A line of rich text produced by wrapping, before it is positioned.
+#[cfg(feature = "truetype")]
+pub(crate) struct RichLine {
+    pub(crate) pieces: Vec<Piece>,
+    /// The span the line started in, whose font sizes the line if it is empty.
+    pub(crate) span: usize,
+    /// Whether this is the last line of a paragraph, which is never justified.
+    pub(crate) last_in_paragraph: bool,
+}
+
+#[cfg(feature = "truetype")]
+impl RichLine {
+    fn new(span: usize) -> Self {
+        Self { pieces: Vec::new(), span, last_in_paragraph: false }
+    }
+
+    /// Returns the width of the line, not counting trailing spaces.
+    pub(crate) fn width(&self) -> f32 {
+        let end = self.pieces.iter().rposition(|p| !p.space).map_or(0, |i| i + 1);
+        self.pieces[..end].iter().map(|p| p.width).sum()
+    }
+
+    /// Returns the text on the line.
+    pub(crate) fn text(&self) -> String {
+        self.pieces.iter().map(|p| p.text.as_str()).collect::<String>().trim_end().to_owned()
+    }
+}
+
+/// Breaks rich text into lines no wider than `max_width`, as measured by `measure`, which is
+/// given the index of the span a piece of text is in. Like `layout::wrap`, lines break at any
+/// whitespace where possible, and inside words that are too long to fit on a line by themselves.
+/// `\n` always starts a new line. When wrapping, each run of other whitespace becomes one space.
+#[cfg(feature = "truetype")]
+pub(crate) fn wrap(rich: &RichText, max_width: Option<f32>, measure: impl Fn(usize, &str) -> f32) -> Vec<RichLine> {
+    let mut lines = Vec::new();
+    let mut line = RichLine::new(0);
+
+    for (span, s) in rich.spans.iter().enumerate() {
+        for (i, paragraph) in s.text.split('\n').enumerate() {
+            if i > 0 {
+                line.last_in_paragraph = true;
+                lines.push(std::mem::replace(&mut line, RichLine::new(span)));
+            }
+            if line.pieces.is_empty() {
+                line.span = span;
+            }
+
+            let mut rest = paragraph;
+            while let Some(c) = rest.chars().next() {
+                let space = c.is_whitespace();
+                let end = rest.find(|c: char| c.is_whitespace() != space).unwrap_or(rest.len());
+                let (text, after) = rest.split_at(end);
+                rest = after;
+
+                // Spaces at the start of a line are dropped when wrapping.
+                if space && line.pieces.is_empty() && max_width.is_some() {
+                    continue;
+                }
+                let mut text = match space && max_width.is_some() {
+                    true => " ".to_owned(),
+                    false => text.to_owned(),
+                };
+                let mut width = measure(span, &text);
+                let line_width = |line: &RichLine| line.pieces.iter().map(|p| p.width).sum::<f32>();
+                if !space && line.width() > 0.0 && max_width.is_some_and(|max| line_width(&line) + width > max) {
+                    lines.push(std::mem::replace(&mut line, RichLine::new(span)));
+                }
+
+                // The word doesn't fit on a line of its own, so break it wherever it overflows.
+                if let Some(max) = max_width.filter(|&max| !space && line_width(&line) + width > max) {
+                    let word = std::mem::take(&mut text);
+                    for c in word.chars() {
+                        text.push(c);
+                        if line_width(&line) + measure(span, &text) > max && text.chars().count() > 1 {
+                            text.pop();
+                            let width = measure(span, &text);
+                            line.pieces.push(Piece { span, text: std::mem::take(&mut text), width, space });
+                            lines.push(std::mem::replace(&mut line, RichLine::new(span)));
+                            text.push(c);
+                        }
+                    }
+                    width = measure(span, &text);
+                }
+                line.pieces.push(Piece { span, text, width, space });
+            }
+        }
+    }
+    line.last_in_paragraph = true;
+    lines.push(line);
+
+    lines
+}
+
+/// Cuts `lines` down to `max_lines`, ending the last line with an ellipsis in the style of its last
+/// piece that still fits in `max_width`.
+#[cfg(feature = "truetype")]
+pub(crate) fn truncate(lines: &mut Vec<RichLine>, max_lines: usize, max_width: Option<f32>, measure: impl Fn(usize, &str) -> f32) {
+    const ELLIPSIS: &str = "…";
+
+    if lines.len() <= max_lines {
+        return;
+    }
+    lines.truncate(max_lines);
+
+    let last = match lines.last_mut() {
+        Some(last) => last,
+        None => return,
+    };
+    last.last_in_paragraph = true;
+
+    loop {
+        while last.pieces.last().is_some_and(|p| p.space) {
+            last.pieces.pop();
+        }
+        let span = last.pieces.last().map_or(last.span, |p| p.span);
+        let width = measure(span, ELLIPSIS);
+        if last.pieces.is_empty() || max_width.is_none_or(|max| last.width() + width <= max) {
+            last.pieces.push(Piece { span, text: ELLIPSIS.to_owned(), width, space: false });
+            return;
+        }
+
+        // Shorten the last piece a character at a time.
+        let piece = last.pieces.last_mut().unwrap();
+        piece.text.pop();
+        if piece.text.is_empty() {
+            last.pieces.pop();
+        } else {
+            piece.width = measure(piece.span, &piece.text);
+        }
+    }
+}
+
+#[cfg(all(test, feature = "truetype"))]
+mod tests {
+    use super::*;
+    use crate::gfx::text::layout;
+
+    fn measure(_span: usize, text: &str) -> f32 {
+        text.chars().count() as f32
+    }
+
+    fn rich_lines(text: &str, max_width: Option<f32>) -> Vec<String> {
+        let style = SpanStyle::new("body", 10.0, &[0u8; 3]);
+        wrap(&RichText::new().with_span(text, &style), max_width, measure).iter().map(RichLine::text).collect()
+    }
+
+    fn plain_lines(text: &str, max_width: Option<f32>) -> Vec<String> {
+        layout::wrap(text, max_width, |text| measure(0, text)).into_iter().map(|line| line.text).collect()
+    }
+
+    #[test]
+    fn breaks_over_long_words() {
+        assert_eq!(rich_lines("a abcdefgh b", Some(3.0)), ["a", "abc", "def", "gh", "b"]);
+        assert_eq!(rich_lines("abc", Some(0.0)), ["a", "b", "c"]);
+    }
+
+    #[test]
+    fn breaks_at_any_whitespace() {
+        assert_eq!(rich_lines("one\ttwo\u{a0}three", Some(7.0)), ["one two", "three"]);
+    }
+
+    #[test]
+    fn wraps_like_plain_text() {
+        for text in ["the quick brown fox\njumps over", "a abcdefgh b", "supercalifragilistic", "", "tab\tseparated  words", "two  spaces\n  indented"] {
+            for max_width in [Some(1.0), Some(4.0), Some(10.0), None] {
+                assert_eq!(rich_lines(text, max_width), plain_lines(text, max_width), "{:?} at {:?}", text, max_width);
+            }
+        }
+    }
+
+    #[test]
+    fn words_break_inside_their_span() {
+        let (a, b) = (SpanStyle::new("a", 10.0, &[0u8; 3]), SpanStyle::new("b", 10.0, &[0u8; 3]));
+        let rich = RichText::new().with_span("xy ", &a).with_span("abcdefg", &b);
+        let lines = wrap(&rich, Some(4.0), measure);
+        assert_eq!(lines.iter().map(RichLine::text).collect::<Vec<_>>(), ["xy", "abcd", "efg"]);
+        assert!(lines[1..].iter().all(|line| line.pieces.iter().all(|piece| piece.span == 1)));
+    }
+}
@@ -0,0 +1,51 @@
+use std::ops::Range;
+use rustybuzz::{Direction, Face, UnicodeBuffer};
+use unicode_bidi::BidiInfo;
+
+/// A glyph picked and positioned by the shaper. Distances are in font units.
+pub(crate) struct ShapedGlyph {
+    pub(crate) id: u16,
+    pub(crate) x_advance: f32,
+    pub(crate) x_offset: f32,
+    /// Upward offset from the baseline, such as for Arabic vowel marks.
+    pub(crate) y_offset: f32,
+}
+
+/// Splits a line of text into runs of a single direction, in the order they are displayed from
+/// left to right. Returns the byte range of each run and whether it is right-to-left. The base
+/// direction of the line comes from its first strong character, so Hebrew text reads right to left
+/// even with English words in it.
+pub(crate) fn visual_runs(text: &str) -> Vec<(Range<usize>, bool)> {
+    let bidi = BidiInfo::new(text, None);
+    let mut runs = Vec::new();
+    for paragraph in &bidi.paragraphs {
+        let (levels, ranges) = bidi.visual_runs(paragraph, paragraph.range.clone());
+        runs.extend(ranges.into_iter().map(|range| {
+            let rtl = levels[range.start].is_rtl();
+            (range, rtl)
+        }));
+    }
+    runs
+}
+
+/// Shapes a run of text in one font and direction, applying ligatures, contextual forms, mark
+/// positioning and kerning. Glyphs are returned in display order, left to right.
+pub(crate) fn shape(data: &[u8], text: &str, rtl: bool) -> Vec<ShapedGlyph> {
+    let face = match Face::from_slice(data, 0) {
+        Some(face) => face,
+        None => return Vec::new(),
+    };
+
+    let mut buffer = UnicodeBuffer::new();
+    buffer.push_str(text);
+    buffer.set_direction(if rtl { Direction::RightToLeft } else { Direction::LeftToRight });
+    buffer.guess_segment_properties();
+
+    let shaped = rustybuzz::shape(&face, &[], buffer);
+    shaped.glyph_infos().iter().zip(shaped.glyph_positions()).map(|(info, pos)| ShapedGlyph {
+        id: info.glyph_id as u16,
+        x_advance: pos.x_advance as f32,
+        x_offset: pos.x_offset as f32,
+        y_offset: pos.y_offset as f32,
+    }).collect()
+}
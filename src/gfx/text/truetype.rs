@@ -0,0 +1,641 @@
+use std::borrow::Cow;
+use std::error::Error;
+use std::fs;
+use std::path::PathBuf;
+use rusttype::{point, Font, GlyphId, Point, PositionedGlyph, Scale};
+use ttf_parser::name_id;
+use crate::gfx::color::Colorful;
+use crate::gfx::text::{composite, emoji, layout, rich, Paragraph, Text};
+use crate::gfx::text::antialias::Rendering;
+use crate::gfx::text::cache::GlyphCache;
+use crate::gfx::text::layout::{Align, InkBounds, LineMetrics, ParagraphStyle, TextMetrics, VerticalAlign};
+use crate::gfx::text::rich::RichText;
+#[cfg(feature = "shaping")]
+use crate::gfx::text::shaping;
+#[cfg(feature = "shaping")]
+use std::ops::Range;
+
+/// Slant of a font face.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum FontStyle {
+    #[default]
+    Normal,
+    Italic,
+    Oblique,
+}
+
+/// What a loaded font looks like, as described by the font file itself.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FontInfo {
+    /// Family name, such as "Open Sans", shared by every weight and style of the font.
+    pub family: String,
+    /// Weight from 100 (thin) to 900 (black). 400 is regular and 700 is bold.
+    pub weight: u16,
+    pub style: FontStyle,
+}
+
+impl FontInfo {
+    /// Reads the family, weight and style from a font file.
+    fn read(data: &[u8]) -> Result<Self, Box<dyn Error>> {
+        let face = ttf_parser::Face::from_slice(data, 0)?;
+        // The typographic family groups more weights together than the legacy family, which is
+        // limited to regular, bold, italic and bold italic.
+        let family = [name_id::TYPOGRAPHIC_FAMILY, name_id::FAMILY].iter()
+            .find_map(|&id| face.names().into_iter().filter(|n| n.name_id == id).find_map(|n| n.to_string()))
+            .unwrap_or_default();
+        let style = match face.style() {
+            ttf_parser::Style::Normal => FontStyle::Normal,
+            ttf_parser::Style::Italic => FontStyle::Italic,
+            ttf_parser::Style::Oblique => FontStyle::Oblique,
+        };
+
+        Ok(Self { family, weight: face.weight().to_number(), style })
+    }
+}
+
+/// Where a font's underline and strikethrough go, as fractions of the text height. Each is the
+/// distance from the baseline up to the top of the line, and the line's thickness.
+#[derive(Clone, Copy)]
+struct Decorations {
+    underline: (f32, f32),
+    strikeout: (f32, f32),
+}
+
+impl Decorations {
+    fn read(data: &[u8]) -> Result<Self, Box<dyn Error>> {
+        let face = ttf_parser::Face::from_slice(data, 0)?;
+        // rusttype sizes text so that the ascent to descent span is the requested height.
+        let units = (face.ascender() - face.descender()).max(1) as f32;
+        let fraction = |m: ttf_parser::LineMetrics| (m.position as f32 / units, m.thickness as f32 / units);
+
+        Ok(Self {
+            underline: face.underline_metrics().map_or((-0.1, 0.05), fraction),
+            strikeout: face.strikeout_metrics().map_or((0.3, 0.05), fraction),
+        })
+    }
+}
+
+/// A font loaded into a `TextRenderer`.
+struct LoadedFont {
+    name: String,
+    /// Identifies the font's glyphs in the glyph cache. Unlike its position in the font list, this
+    /// never changes or gets reused.
+    id: usize,
+    info: FontInfo,
+    decorations: Decorations,
+    /// Owns the font file, unless it was static data.
+    font: Font<'static>,
+    /// The font file when it was static data, which `font` borrows.
+    static_data: Option<&'static [u8]>,
+    /// Whether the font has color bitmap glyphs, such as emoji.
+    color: bool,
+}
+
+impl LoadedFont {
+    /// Returns the font file, for reading tables rusttype doesn't understand.
+    fn data(&self) -> &[u8] {
+        match &self.font {
+            Font::Owned(face) => face.as_slice(),
+            Font::Ref(_) => self.static_data.unwrap_or_default(),
+        }
+    }
+}
+
+/// A glyph placed on a line, and the index of the font it came from.
+struct PlacedGlyph {
+    font: usize,
+    glyph: PositionedGlyph<'static>,
+}
+
+/// Can render text with TrueType and OpenType fonts. Fonts can be loaded and unloaded at any time.
+#[derive(Default)]
+pub struct TextRenderer {
+    font_cache: Vec<LoadedFont>,
+    /// Id for the next font loaded.
+    next_id: usize,
+    /// Fonts to try, in order, for characters a font doesn't have.
+    fallbacks: Vec<(String, Vec<String>)>,
+    /// Rasterized glyphs, shared by every font and size.
+    glyph_cache: GlyphCache,
+    rendering: Rendering,
+}
+
+impl TextRenderer {
+    /// Loads a font with a given name, replacing any font with the same name. Takes either font data
+    /// the renderer owns, such as a `Vec<u8>`, or static data from `include_bytes!`. Color emoji
+    /// fonts with CBDT or sbix bitmaps are supported.
+    pub fn load_font(&mut self, named: &str, data: impl Into<Cow<'static, [u8]>>) -> Result<(), Box<dyn Error>> {
+        let data = data.into();
+        let info = FontInfo::read(&data)?;
+        let decorations = Decorations::read(&data)?;
+        let color = emoji::has_color_glyphs(&data);
+        // Owned bytes move into the font rather than being copied.
+        let (font, static_data) = match data {
+            Cow::Borrowed(bytes) => (Font::try_from_bytes(bytes), Some(bytes)),
+            Cow::Owned(vec) => (Font::try_from_vec(vec), None),
+        };
+        let font = font.ok_or_else(|| format!("`{}` is not a usable font", named))?;
+
+        self.unload_font(named);
+        self.font_cache.push(LoadedFont {
+            name: named.to_owned(),
+            id: self.next_id,
+            info,
+            decorations,
+            font,
+            static_data,
+            color,
+        });
+        self.next_id += 1;
+        Ok(())
+    }
+
+    /// Loads a font file with a given name. See `load_font`.
+    pub fn load_font_file(&mut self, named: &str, path: PathBuf) -> Result<(), Box<dyn Error>> {
+        self.load_font(named, fs::read(path)?)
+    }
+
+    /// Unloads a font and forgets its cached glyphs. Returns false if there is no such font. Fonts
+    /// that fall back to it carry on without it.
+    pub fn unload_font(&mut self, named: &str) -> bool {
+        match self.font_index(named) {
+            Some(idx) => {
+                let font = self.font_cache.remove(idx);
+                self.glyph_cache.remove_font(font.id);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Returns the family, weight and style of a loaded font.
+    pub fn font_info(&self, named: &str) -> Option<&FontInfo> {
+        self.font_index(named).map(|idx| &self.font_cache[idx].info)
+    }
+
+    /// Finds the loaded font of a family that best matches a weight and style, much like CSS does.
+    /// The closest weight in the requested style wins, then italic and oblique stand in for each
+    /// other, and lastly upright fonts are used. Returns the name the font was loaded with.
+    pub fn match_font(&self, family: &str, weight: u16, style: FontStyle) -> Option<&str> {
+        let style_rank = |s: FontStyle| match (style, s) {
+            (wanted, s) if wanted == s => 0,
+            (FontStyle::Italic, FontStyle::Oblique) | (FontStyle::Oblique, FontStyle::Italic) => 1,
+            _ => 2,
+        };
+
+        self.font_cache.iter()
+            .filter(|f| f.info.family.eq_ignore_ascii_case(family))
+            .min_by_key(|f| (style_rank(f.info.style), f.info.weight.abs_diff(weight)))
+            .map(|f| f.name.as_str())
+    }
+
+    /// Sets the fonts to fall back to, in order, for characters that `named` doesn't have. For
+    /// example an emoji font, then a font covering other scripts. Fallbacks don't need to be loaded
+    /// yet, and ones that never get loaded are skipped.
+    pub fn set_fallbacks(&mut self, named: &str, fallbacks: &[&str]) {
+        let fallbacks = fallbacks.iter().map(|f| f.to_string()).collect();
+        match self.fallbacks.iter_mut().find(|(name, _)| name == named) {
+            Some((_, existing)) => *existing = fallbacks,
+            None => self.fallbacks.push((named.to_owned(), fallbacks)),
+        }
+    }
+
+    /// Returns a font with a given name and its index, or the first font loaded if there's no such
+    /// font, such as one that was unloaded. At least one font must be loaded.
+    fn find_font(&self, named: &str) -> (usize, &Font<'static>) {
+        let idx = self.font_index(named).unwrap_or(0);
+        (idx, &self.font_cache[idx].font)
+    }
+
+    /// Returns empty text, for when no fonts are loaded.
+    fn empty_paragraph() -> Paragraph {
+        Paragraph { text: Text::new("", Vec::new(), None, 0, &[0u8; 4]), height: 0, lines: Vec::new() }
+    }
+
+    fn font_index(&self, named: &str) -> Option<usize> {
+        self.font_cache.iter().position(|f| f.name == named)
+    }
+
+    /// Returns the indices of a font and each of its loaded fallbacks.
+    fn font_chain(&self, named: &str) -> Vec<usize> {
+        let (primary, _) = self.find_font(named);
+        let fallbacks = self.fallbacks.iter()
+            .find(|(name, _)| name == named)
+            .map(|(_, fallbacks)| fallbacks.as_slice())
+            .unwrap_or_default();
+
+        std::iter::once(primary)
+            .chain(fallbacks.iter().filter_map(|f| self.font_index(f)))
+            .collect()
+    }
+
+    /// Returns the first font in `chain` that has a character, or the first font if none do.
+    fn font_for(&self, chain: &[usize], c: char) -> usize {
+        chain.iter()
+            .copied()
+            .find(|&idx| self.font_cache[idx].font.glyph(c).id().0 != 0)
+            .unwrap_or(chain[0])
+    }
+
+    /// Lays out a single line of text starting at `origin`, picking the first font in `chain` that
+    /// has each character. Characters no font has use the first font's missing glyph box.
+    #[cfg(not(feature = "shaping"))]
+    fn layout_line(&self, chain: &[usize], text: &str, scale: Scale, origin: Point<f32>) -> Vec<PlacedGlyph> {
+        let mut glyphs = Vec::with_capacity(text.len());
+        let mut caret = origin.x;
+        let mut last: Option<(usize, GlyphId)> = None;
+
+        for c in text.chars().filter(|c| !c.is_control()) {
+            let font_idx = self.font_for(chain, c);
+            let font = &self.font_cache[font_idx].font;
+            let glyph = font.glyph(c).scaled(scale);
+
+            // Kerning only makes sense between glyphs of the same font.
+            if let Some((last_font, last_id)) = last {
+                if last_font == font_idx {
+                    caret += font.pair_kerning(scale, last_id, glyph.id());
+                }
+            }
+            last = Some((font_idx, glyph.id()));
+
+            let advance = glyph.h_metrics().advance_width;
+            glyphs.push(PlacedGlyph { font: font_idx, glyph: glyph.positioned(point(caret, origin.y)) });
+            caret += advance;
+        }
+
+        glyphs
+    }
+
+    /// Lays out a single line of text starting at `origin` with a shaper, so scripts that join or
+    /// reorder letters display properly. Right-to-left runs are reversed into display order first.
+    /// Fonts are picked from `chain` as in the unshaped layout, but marks stay in the font of the
+    /// letter they belong to where it has them.
+    #[cfg(feature = "shaping")]
+    fn layout_line(&self, chain: &[usize], text: &str, scale: Scale, origin: Point<f32>) -> Vec<PlacedGlyph> {
+        let text: String = text.chars().filter(|c| !c.is_control()).collect();
+        let mut glyphs = Vec::with_capacity(text.len());
+        let mut caret = origin.x;
+
+        for (range, rtl) in shaping::visual_runs(&text) {
+            let run = &text[range];
+
+            // Split the run where the font changes, keeping characters in the current font for as
+            // long as it has them.
+            let mut segments: Vec<(usize, Range<usize>)> = Vec::new();
+            for (i, c) in run.char_indices() {
+                let font = match segments.last() {
+                    Some(&(current, _)) if self.font_cache[current].font.glyph(c).id().0 != 0 => current,
+                    _ => self.font_for(chain, c),
+                };
+                match segments.last_mut() {
+                    Some((current, segment)) if *current == font => segment.end = i + c.len_utf8(),
+                    _ => segments.push((font, i..i + c.len_utf8())),
+                }
+            }
+            if rtl {
+                segments.reverse();
+            }
+
+            for (font_idx, segment) in segments {
+                let loaded = &self.font_cache[font_idx];
+                // Shaped positions are in font units, which rusttype scales by the ascent to
+                // descent span.
+                let v_metrics = loaded.font.v_metrics_unscaled();
+                let units = scale.y / (v_metrics.ascent - v_metrics.descent);
+
+                for shaped in shaping::shape(loaded.data(), &run[segment], rtl) {
+                    let glyph = loaded.font.glyph(GlyphId(shaped.id)).scaled(scale);
+                    let position = point(caret + shaped.x_offset * units, origin.y - shaped.y_offset * units);
+                    glyphs.push(PlacedGlyph { font: font_idx, glyph: glyph.positioned(position) });
+                    caret += shaped.x_advance * units;
+                }
+            }
+        }
+
+        glyphs
+    }
+
+    /// Returns the cache of rasterized glyphs, e.g. to clear it after drawing a lot of one-off text.
+    pub fn glyph_cache(&self) -> &GlyphCache {
+        &self.glyph_cache
+    }
+
+    /// Changes how glyphs are rasterized, such as gamma for thin fonts or LCD subpixel rendering.
+    /// Empties the glyph cache, so set it once at startup.
+    pub fn set_rendering(&mut self, rendering: Rendering) {
+        self.rendering = rendering;
+        self.glyph_cache.clear();
+    }
+
+    pub fn rendering(&self) -> &Rendering {
+        &self.rendering
+    }
+
+    /// Renders a line of text. Fonts that aren't loaded fall back to the first font loaded, and
+    /// nothing is drawn if there are no fonts at all.
+    pub fn render(&self, text: &str, font: &str, height: f32, color: &impl Colorful) -> Text {
+        if self.font_cache.is_empty() {
+            return Self::empty_paragraph().text;
+        }
+        let chain = self.font_chain(font);
+        let (_, font) = self.find_font(font);
+
+        // Render some text
+        let font_h_int = height.ceil() as usize;
+        let font_scale = Scale::uniform(height);
+
+        // Get the height
+        let v_metrics = font.v_metrics(font_scale);
+        let offset = point(0.0, v_metrics.ascent);
+
+        // Load glyphs
+        let glyphs = self.layout_line(&chain, text, font_scale, offset);
+
+        // Get the width
+        let width = Self::measure_glyphs(&glyphs).ceil() as usize;
+
+        // Draw the text into a texture.
+        let (coverage, color_glyphs, lcd) = self.draw_glyphs(&glyphs, width, font_h_int);
+        let mut text = Text::new(text, coverage, color_glyphs, width, color);
+        text.lcd = lcd;
+        text
+    }
+
+    /// Measures a line of text as `render` would draw it, without rasterizing any glyphs. Ink
+    /// bounds come from glyph outlines, so don't include color bitmap glyphs such as emoji.
+    pub fn measure(&self, text: &str, font: &str, height: f32) -> TextMetrics {
+        if self.font_cache.is_empty() {
+            return TextMetrics::default();
+        }
+        let chain = self.font_chain(font);
+        let (_, font) = self.find_font(font);
+        let font_scale = Scale::uniform(height);
+        let v_metrics = font.v_metrics(font_scale);
+
+        let glyphs = self.layout_line(&chain, text, font_scale, point(0.0, v_metrics.ascent));
+        let ink = glyphs.iter()
+            .filter_map(|g| g.glyph.unpositioned().exact_bounding_box().map(|bb| (g.glyph.position(), bb)))
+            .map(|(p, bb)| InkBounds { left: p.x + bb.min.x, top: p.y + bb.min.y, right: p.x + bb.max.x, bottom: p.y + bb.max.y })
+            .reduce(InkBounds::union);
+
+        TextMetrics {
+            advance: Self::measure_glyphs(&glyphs),
+            ink,
+            ascent: v_metrics.ascent,
+            descent: -v_metrics.descent,
+            line_gap: v_metrics.line_gap,
+            baseline: v_metrics.ascent,
+        }
+    }
+
+    /// Lays out multiple lines of text within a box and renders them. `\n` starts a new line, and
+    /// lines are wrapped, aligned and truncated as described by `style`.
+    pub fn render_paragraph(&self, text: &str, font: &str, height: f32, color: &impl Colorful, style: &ParagraphStyle) -> Paragraph {
+        if self.font_cache.is_empty() {
+            return Self::empty_paragraph();
+        }
+        let chain = self.font_chain(font);
+        let (_, font) = self.find_font(font);
+        let font_scale = Scale::uniform(height);
+        let v_metrics = font.v_metrics(font_scale);
+        let measure = |s: &str| Self::measure_glyphs(&self.layout_line(&chain, s, font_scale, point(0.0, 0.0)));
+
+        let line_height = v_metrics.ascent - v_metrics.descent + v_metrics.line_gap;
+        let (width, bitmap_height, lines) = layout::place_paragraph(text, style, v_metrics.ascent, line_height, measure);
+
+        // Position the glyphs of every word.
+        let mut glyphs = Vec::new();
+        let mut metrics = Vec::with_capacity(lines.len());
+        for line in lines {
+            for (word, x) in line.words {
+                glyphs.extend(self.layout_line(&chain, &word, font_scale, point(x, line.metrics.baseline)));
+            }
+            metrics.push(line.metrics);
+        }
+
+        let (coverage, color_glyphs, lcd) = self.draw_glyphs(&glyphs, width, bitmap_height);
+        let mut text = Text::new(text, coverage, color_glyphs, width, color);
+        text.lcd = lcd;
+
+        Paragraph {
+            text,
+            height: bitmap_height,
+            lines: metrics,
+        }
+    }
+
+    /// Lays out and renders rich text, where every span has its own font, size, color and
+    /// decorations. Spans on the same line share a baseline, and each line is as tall as its
+    /// biggest font. Wrapping and alignment work as in `render_paragraph`. Every span is drawn in
+    /// its own color, which `Screen::draw_text` keeps, ignoring the paint it is given.
+    pub fn render_rich(&self, rich: &RichText, style: &ParagraphStyle) -> Paragraph {
+        if rich.spans.is_empty() || self.font_cache.is_empty() {
+            return Self::empty_paragraph();
+        }
+
+        // Font chain, scale and metrics of every span.
+        let runs: Vec<_> = rich.spans.iter().map(|span| {
+            let chain = self.font_chain(&span.style.font);
+            let scale = Scale::uniform(span.style.size);
+            let v_metrics = self.font_cache[chain[0]].font.v_metrics(scale);
+            (chain, scale, v_metrics)
+        }).collect();
+        let measure = |span: usize, s: &str| {
+            let (chain, scale, _) = &runs[span];
+            Self::measure_glyphs(&self.layout_line(chain, s, *scale, point(0.0, 0.0)))
+        };
+
+        let max_width = style.max_width.map(|w| w as f32);
+        let mut lines = rich::wrap(rich, max_width, measure);
+        if let Some(max_lines) = style.max_lines {
+            rich::truncate(&mut lines, max_lines, max_width, measure);
+        }
+
+        // Each line is sized by the tallest font on it.
+        let line_sizes: Vec<(f32, f32)> = lines.iter().map(|line| {
+            let spans: Vec<usize> = match line.pieces.is_empty() {
+                true => vec![line.span],
+                false => line.pieces.iter().map(|p| p.span).collect(),
+            };
+            let (ascent, descent, line_gap) = spans.iter()
+                .map(|&s| runs[s].2)
+                .fold((0.0f32, 0.0f32, 0.0f32), |(a, d, g), v| (a.max(v.ascent), d.max(-v.descent), g.max(v.line_gap)));
+            (ascent, (ascent + descent + line_gap) * style.line_spacing)
+        }).collect();
+
+        let width = style.max_width
+            .unwrap_or_else(|| lines.iter().map(|l| l.width()).fold(0.0, f32::max).ceil() as usize);
+        let text_height: f32 = line_sizes.iter().map(|(_, h)| h).sum();
+        let bitmap_height = style.box_height.unwrap_or(text_height.ceil() as usize);
+        let mut top = match style.vertical_align {
+            VerticalAlign::Top => 0.0,
+            VerticalAlign::Middle => (bitmap_height as f32 - text_height) / 2.0,
+            VerticalAlign::Bottom => bitmap_height as f32 - text_height,
+        };
+
+        // Position the glyphs and decorations of every piece, grouped by span so each can be
+        // drawn in its own color.
+        let mut glyphs: Vec<Vec<PlacedGlyph>> = rich.spans.iter().map(|_| Vec::new()).collect();
+        let mut bars: Vec<Vec<(f32, f32, f32, f32)>> = rich.spans.iter().map(|_| Vec::new()).collect();
+        let mut metrics = Vec::with_capacity(lines.len());
+        for (line, (ascent, line_height)) in lines.iter().zip(line_sizes) {
+            let natural_width = line.width();
+            let justify = style.align == Align::Justify && !line.last_in_paragraph && style.max_width.is_some();
+            let line_width = if justify { width as f32 } else { natural_width };
+            let x = match style.align {
+                Align::Left | Align::Justify => 0.0,
+                Align::Center => (width as f32 - line_width) / 2.0,
+                Align::Right => width as f32 - line_width,
+            };
+            let baseline = top + ascent;
+
+            // Trailing spaces aren't drawn, and the spaces between words stretch when justifying.
+            let end = line.pieces.iter().rposition(|p| !p.space).map_or(0, |i| i + 1);
+            let spaces = line.pieces[..end].iter().filter(|p| p.space).count();
+            let stretch = if justify && spaces > 0 { (line_width - natural_width) / spaces as f32 } else { 0.0 };
+
+            let mut pen = x;
+            for piece in &line.pieces[..end] {
+                let (chain, scale, _) = &runs[piece.span];
+                let advance = piece.width + if piece.space { stretch } else { 0.0 };
+                if !piece.space {
+                    glyphs[piece.span].extend(self.layout_line(chain, &piece.text, *scale, point(pen, baseline)));
+                }
+
+                let span_style = &rich.spans[piece.span].style;
+                let decorations = self.font_cache[chain[0]].decorations;
+                for (enabled, (position, thickness)) in [(span_style.underline, decorations.underline), (span_style.strikethrough, decorations.strikeout)] {
+                    if enabled {
+                        bars[piece.span].push((pen, pen + advance, baseline - position * scale.y, thickness * scale.y));
+                    }
+                }
+                pen += advance;
+            }
+
+            metrics.push(LineMetrics { text: line.text(), x, top, baseline, width: line_width, height: line_height });
+            top += line_height;
+        }
+
+        // Draw each span in its own color onto one layer.
+        let mut layer = vec![0u8; width * bitmap_height * 4];
+        for (span, (glyphs, bars)) in rich.spans.iter().zip(glyphs.iter().zip(&bars)) {
+            if glyphs.is_empty() && bars.is_empty() {
+                continue;
+            }
+            let (mut coverage, color_glyphs, _) = self.draw_glyphs(glyphs, width, bitmap_height);
+            for &(x0, x1, y, thickness) in bars {
+                let rows = y.round().max(0.0) as usize..((y + thickness).round().max(y.round() + 1.0).max(0.0) as usize).min(bitmap_height);
+                // Pieces can be placed past the right edge, such as spaces that never wrap.
+                let cols = x0.round().clamp(0.0, width as f32) as usize..x1.round().clamp(0.0, width as f32) as usize;
+                if cols.is_empty() {
+                    continue;
+                }
+                for row in rows {
+                    coverage[row * width + cols.start..row * width + cols.end].fill(u8::MAX);
+                }
+            }
+
+            let (r, g, b, a) = span.style.color.as_rgba();
+            for (dst, &v) in layer.chunks_mut(4).zip(&coverage) {
+                if v > 0 {
+                    composite(dst, &[r, g, b, (a as u16 * v as u16 / u8::MAX as u16) as u8]);
+                }
+            }
+            if let Some(color_glyphs) = color_glyphs {
+                for (dst, src) in layer.chunks_mut(4).zip(color_glyphs.chunks(4)) {
+                    composite(dst, src);
+                }
+            }
+        }
+
+        // Everything keeps its span's color, so nothing is left for `draw_text` to color.
+        let coverage = vec![0u8; width * bitmap_height];
+        Paragraph {
+            text: Text::new(&rich.plain_text(), coverage, Some(layer), width, &[0u8; 4]),
+            height: bitmap_height,
+            lines: metrics,
+        }
+    }
+
+    /// Returns the distance from the origin of the first glyph to the end of the last glyph.
+    fn measure_glyphs(glyphs: &[PlacedGlyph]) -> f32 {
+        glyphs
+            .iter()
+            .rev()
+            .map(|g| g.glyph.position().x + g.glyph.unpositioned().h_metrics().advance_width)
+            .next()
+            .unwrap_or(0.0)
+    }
+
+    /// Draws placed glyphs into a coverage mask of the given size, using cached masks where
+    /// possible. Color glyphs are drawn into a separate RGBA layer instead, which is only allocated
+    /// if the text has any. With LCD rendering, the subpixel coverage is returned too.
+    fn draw_glyphs(&self, glyphs: &[PlacedGlyph], width: usize, height: usize) -> (Vec<u8>, Option<Vec<u8>>, Option<Vec<u8>>) {
+        let mut coverage = vec![0u8; width * height];
+        let mut color_glyphs: Option<Vec<u8>> = None;
+        let mut lcd = self.rendering.lcd.map(|_| vec![0u8; width * height * 3]);
+
+        for placed in glyphs {
+            let font = &self.font_cache[placed.font];
+            let (mask, gx, gy) = self.glyph_cache.get(font.id, &placed.glyph, font.color.then_some(font.data()), &self.rendering);
+            for (idx, &v) in mask.coverage.iter().enumerate() {
+                if v == 0 {
+                    continue;
+                }
+                let x = gx + mask.left + (idx % mask.width) as i32;
+                let y = gy + mask.top + (idx / mask.width) as i32;
+                // There's still a possibility that the glyph clips the boundaries of the bitmap
+                if x >= 0 && x < width as i32 && y >= 0 && y < height as i32 {
+                    let off = x as usize + y as usize * width;
+                    match &mask.rgba {
+                        Some(rgba) => {
+                            let layer = color_glyphs.get_or_insert_with(|| vec![0u8; width * height * 4]);
+                            composite(&mut layer[off * 4..off * 4 + 4], &rgba[idx * 4..idx * 4 + 4]);
+                        }
+                        // Glyphs that overlap, like tight italics, keep the stronger coverage.
+                        None => coverage[off] = coverage[off].max(v),
+                    }
+                    if let (Some(lcd), Some(subpixels)) = (&mut lcd, &mask.lcd) {
+                        for c in 0..3 {
+                            lcd[off * 3 + c] = lcd[off * 3 + c].max(subpixels[idx * 3 + c]);
+                        }
+                    }
+                }
+            }
+        }
+
+        (coverage, color_glyphs, lcd)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gfx::text::rich::SpanStyle;
+
+    fn renderer() -> TextRenderer {
+        let mut renderer = TextRenderer::default();
+        renderer.load_font("body", &include_bytes!("../../OpenSans-CondLight.ttf")[..]).unwrap();
+        renderer
+    }
+
+    #[test]
+    fn owned_fonts_render_after_other_fonts_move() {
+        let mut renderer = renderer();
+        renderer.load_font("owned", include_bytes!("../../OpenSans-CondLight.ttf").to_vec()).unwrap();
+        let before = renderer.render("Hello", "owned", 20.0, &[255u8; 3]);
+        // Moves the owned font within the font list.
+        renderer.unload_font("body");
+        let after = renderer.render("Hello", "owned", 20.0, &[255u8; 3]);
+        assert!(before.width > 0);
+        assert_eq!(before.coverage, after.coverage);
+    }
+
+    #[test]
+    fn underlines_past_the_edge_are_clipped() {
+        let style = SpanStyle::new("body", 20.0, &[255u8; 3]).with_underline();
+        let rich = RichText::new().with_span("                    word", &style);
+        let paragraph = renderer().render_rich(&rich, &ParagraphStyle { max_width: Some(10), ..Default::default() });
+        assert_eq!(paragraph.text.width, 10);
+    }
+}
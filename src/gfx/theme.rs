@@ -0,0 +1,162 @@
+use std::error::Error;
+use std::fs;
+use std::path::PathBuf;
+use crate::gfx::color::Color;
+
+/// A font family and size to render a kind of text with.
+#[derive(Clone, Debug, PartialEq)]
+pub struct FontRole {
+    /// Name the font was loaded with in the `TextRenderer`.
+    pub family: String,
+    /// Height of the text in pixels.
+    pub size: f32,
+}
+
+impl FontRole {
+    pub fn new(family: &str, size: f32) -> Self {
+        Self { family: family.to_owned(), size }
+    }
+}
+
+/// The colors and fonts an app is drawn with. Drawing code should ask the theme for a role, such
+/// as `theme.accent`, instead of naming a color, so the whole app can change look at once.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Theme {
+    pub name: String,
+    /// Behind everything.
+    pub background: Color,
+    /// Panels, buttons and cards that sit on the background.
+    pub surface: Color,
+    /// Highlights and the thing the user should look at.
+    pub accent: Color,
+    /// Text drawn on the background or a surface.
+    pub text: Color,
+    /// Destructive actions and errors.
+    pub danger: Color,
+    /// Labels and paragraphs.
+    pub body_font: FontRole,
+    /// Headings.
+    pub title_font: FontRole,
+}
+
+impl Theme {
+    /// Dark theme, easy on the eyes at bedtime.
+    pub fn night() -> Self {
+        Self {
+            name: "night".to_owned(),
+            background: Color((0, 0, 0, u8::MAX)),
+            surface: Color((48, 48, 56, u8::MAX)),
+            accent: Color((u8::MAX, u8::MAX, 0, u8::MAX)),
+            text: Color((u8::MAX, u8::MAX, u8::MAX, u8::MAX)),
+            danger: Color((u8::MAX, 64, 64, u8::MAX)),
+            body_font: FontRole::new("OpenSans-CondLight", 18.0),
+            title_font: FontRole::new("OpenSans-CondLight", 32.0),
+        }
+    }
+
+    /// Light theme for daytime.
+    pub fn day() -> Self {
+        Self {
+            name: "day".to_owned(),
+            background: Color((250, 250, 245, u8::MAX)),
+            surface: Color((u8::MAX, u8::MAX, u8::MAX, u8::MAX)),
+            accent: Color((0, 112, 224, u8::MAX)),
+            text: Color((24, 24, 24, u8::MAX)),
+            danger: Color((208, 0, 0, u8::MAX)),
+            ..Self::night()
+        }
+    }
+
+    /// Loads a theme from a file. See `parse` for the format.
+    pub fn load(path: PathBuf) -> Result<Self, Box<dyn Error>> {
+        Self::parse(&fs::read_to_string(path)?)
+    }
+
+    /// Parses a theme from `key = value` lines. Colors can be written in any format `Color`
+    /// parses. Blank lines and lines starting with `//` are ignored, and anything not given is
+    /// taken from the night theme. For example:
+    ///
+    /// ```text
+    /// name = day
+    /// background = #fafaf5
+    /// accent = dodgerblue
+    /// body_font = OpenSans-CondLight
+    /// body_size = 18
+    /// ```
+    pub fn parse(text: &str) -> Result<Self, Box<dyn Error>> {
+        let mut theme = Self::night();
+
+        for (num, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with("//") {
+                continue;
+            }
+
+            let (key, value) = line.split_once('=')
+                .ok_or_else(|| format!("line {}: expected `key = value`", num + 1))?;
+            let (key, value) = (key.trim(), value.trim());
+
+            match key {
+                "name" => theme.name = value.to_owned(),
+                "background" => theme.background = value.parse()?,
+                "surface" => theme.surface = value.parse()?,
+                "accent" => theme.accent = value.parse()?,
+                "text" => theme.text = value.parse()?,
+                "danger" => theme.danger = value.parse()?,
+                "body_font" => theme.body_font.family = value.to_owned(),
+                "body_size" => theme.body_font.size = value.parse()?,
+                "title_font" => theme.title_font.family = value.to_owned(),
+                "title_size" => theme.title_font.size = value.parse()?,
+                _ => return Err(format!("line {}: unknown theme key `{}`", num + 1, key).into()),
+            }
+        }
+
+        Ok(theme)
+    }
+}
+
+/// A set of themes with one active at a time, for switching looks while the app runs (e.g. day and
+/// night).
+pub struct Themes {
+    themes: Vec<Theme>,
+    current: usize,
+}
+
+impl Default for Themes {
+    /// Starts with the night theme active and the day theme available.
+    fn default() -> Self {
+        Self { themes: vec![Theme::night(), Theme::day()], current: 0 }
+    }
+}
+
+impl Themes {
+    /// Adds a theme, replacing any theme with the same name.
+    pub fn add(&mut self, theme: Theme) {
+        match self.themes.iter().position(|t| t.name == theme.name) {
+            Some(idx) => self.themes[idx] = theme,
+            None => self.themes.push(theme),
+        }
+    }
+
+    /// Returns the active theme.
+    pub fn current(&self) -> &Theme {
+        &self.themes[self.current]
+    }
+
+    /// Activates the theme with the given name. Returns false if there is no such theme.
+    pub fn switch(&mut self, name: &str) -> bool {
+        match self.themes.iter().position(|t| t.name == name) {
+            Some(idx) => {
+                self.current = idx;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Activates the next theme, wrapping around. Handy for a single theme toggle button.
+    pub fn cycle(&mut self) -> &Theme {
+        self.current = (self.current + 1) % self.themes.len();
+        self.current()
+    }
+}
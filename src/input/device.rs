@@ -2,26 +2,83 @@ use std::error::Error;
 use std::fs::File;
 use std::io::Read;
 use std::ops::{Deref, DerefMut};
-use crate::{EVENT_BUFFER_LEN, EVENT_SIZE};
+use std::time::Duration;
+use crate::EVENT_BUFFER_LEN;
+use crate::input::event::Event;
+
+/// Size of the kernel's `struct input_event`. Its timestamp is two `long`s, 8 bytes each on 64-bit
+/// targets. 32-bit targets keep 4 byte fields even with a 64-bit `time_t`, since the kernel stores
+/// the time as unsigned to last past 2038.
+#[cfg(target_pointer_width = "64")]
+pub(crate) const EVENT_SIZE: usize = 24;
+#[cfg(not(target_pointer_width = "64"))]
+pub(crate) const EVENT_SIZE: usize = 16;
+
+/// Size of each of the two timestamp fields that start an event.
+const TIME_FIELD_SIZE: usize = (EVENT_SIZE - 8) / 2;
 
 pub trait ReadInputStream {
     fn read_events(&mut self, stream: impl Iterator<Item = InputEvent>) -> Result<bool, Box<dyn Error>>;
 }
 
-/// Represents a raw input event from the Linux evdev system.
+/// Represents a raw input event from the Linux evdev system. Use `event` to find out what it means.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
 pub struct InputEvent {
-    pub r#type: u8,
-    pub code: u8,
+    /// When the kernel generated the event, since the epoch of the device's clock (wall clock time
+    /// by default). Events of one `EV_SYN` report share the same time.
+    pub time: Duration,
+    pub r#type: u16,
+    pub code: u16,
     pub value: i32,
 }
 
+impl InputEvent {
+    /// Decodes a `struct input_event` for the target: a `timeval` of two `long`s, then 16-bit type
+    /// and code and a 32-bit value, all in native byte order.
+    pub(crate) fn from_bytes(raw: &[u8]) -> Self {
+        Self::decode(raw, TIME_FIELD_SIZE)
+    }
+
+    /// Decodes an event whose timestamp fields are `time_field_size` bytes each.
+    fn decode(raw: &[u8], time_field_size: usize) -> Self {
+        let sec = Self::time_field(&raw[..time_field_size]);
+        let usec = Self::time_field(&raw[time_field_size..time_field_size * 2]);
+        let rest = &raw[time_field_size * 2..];
+        Self {
+            time: Duration::from_secs(sec) + Duration::from_micros(usec),
+            r#type: u16::from_ne_bytes([rest[0], rest[1]]),
+            code: u16::from_ne_bytes([rest[2], rest[3]]),
+            value: i32::from_ne_bytes([rest[4], rest[5], rest[6], rest[7]]),
+        }
+    }
+
+    /// Decodes the type and code of the event.
+    pub fn event(&self) -> Event {
+        Event::from(self)
+    }
+
+    /// Reads one of the timestamp's fields, which is 4 or 8 bytes.
+    fn time_field(bytes: &[u8]) -> u64 {
+        let mut field = [0u8; 8];
+        if cfg!(target_endian = "little") {
+            field[..bytes.len()].copy_from_slice(bytes);
+        } else {
+            field[8 - bytes.len()..].copy_from_slice(bytes);
+        }
+        u64::from_ne_bytes(field)
+    }
+}
+
 /// Represents a generic input device
-pub struct InputDevice<T: ReadInputStream> {
+pub struct InputDevice<T: ReadInputStream, R: Read = File> {
     /// Handle to file for the input device.
-    file: File,
-    /// Buffer for reading input data. Event structure is 8 bytes timestamp, 2 bytes type, 2 bytes
-    /// code, and 4 bytes for value. 16 bytes per event with a 16 byte buffer.
+    file: R,
+    /// Buffer for reading input data. Event structure is a 8 or 16 byte timestamp, 2 bytes type, 2
+    /// bytes code, and 4 bytes for value. Room for `EVENT_BUFFER_LEN` events.
     data: [u8; EVENT_SIZE * EVENT_BUFFER_LEN],
+    /// How many bytes at the start of `data` are left over from the last read, the start of an
+    /// event that was cut off.
+    pending: usize,
     /// ..
     device: T,
 }
@@ -29,9 +86,17 @@ pub struct InputDevice<T: ReadInputStream> {
 impl<T> InputDevice<T> where T: ReadInputStream + Default {
     /// Creates an Input device with the default implementation of the InputDevice.
     pub(crate) fn new(file: File) -> Self {
+        Self::from_reader(file)
+    }
+}
+
+impl<T, R> InputDevice<T, R> where T: ReadInputStream + Default, R: Read {
+    /// Creates an Input device that reads events from anything, such as a recording.
+    pub(crate) fn from_reader(file: R) -> Self {
         Self {
             file,
             data: [0; EVENT_SIZE * EVENT_BUFFER_LEN],
+            pending: 0,
             device: T::default(),
         }
     }
@@ -40,31 +105,32 @@ impl<T> InputDevice<T> where T: ReadInputStream + Default {
     /// indicate whether or not there's anything to process on the user's end. An error return type
     /// indicates that the user should terminate the process, or gracefully handle the error.
     pub fn poll(&mut self) -> Result<bool, Box<dyn Error>> {
-        // Read up to N events into buffer.
-        let bytes_read = self.file.read(&mut self.data)?;
+        // Read up to N events into buffer, after any partial event from the last read.
+        let bytes_read = self.file.read(&mut self.data[self.pending..])?;
         if bytes_read == 0 {
             return Ok(false);
         }
 
-        // Take a reference to the slice that only contains data read.
-        let events = &self.data[..bytes_read];
+        // Only whole events are decoded. The rest waits for the next read to finish it.
+        let filled = self.pending + bytes_read;
+        let complete = filled - filled % EVENT_SIZE;
 
         // Turn a simple array of bytes into an iterator over well-formed events.
-        // TODO: Check to make sure that creating InputEvent structs from this is not slow.
-        let events = events.chunks(EVENT_SIZE).map(|raw_event| InputEvent {
-            r#type: raw_event[8],
-            code: raw_event[10],
-            value: i32::from_le_bytes([raw_event[12], raw_event[13], raw_event[14], raw_event[15]]),
-        });
+        let events = self.data[..complete].chunks_exact(EVENT_SIZE).map(InputEvent::from_bytes);
 
-        // Pass to the device abstraction and return the result.
-        Ok(self.device.read_events(events)?)
+        // Pass to the device abstraction. The buffer is moved along even if that fails, so the same
+        // events aren't read twice.
+        let result = self.device.read_events(events);
+
+        self.data.copy_within(complete..filled, 0);
+        self.pending = filled - complete;
+        result
     }
 }
 
 /// Implementing Deref allows us to use the inner device as if it's fields are part of the base
 /// struct.
-impl<T: ReadInputStream> Deref for InputDevice<T> {
+impl<T: ReadInputStream, R: Read> Deref for InputDevice<T, R> {
     type Target = T;
     fn deref(&self) -> &Self::Target {
         &self.device
@@ -72,8 +138,114 @@ impl<T: ReadInputStream> Deref for InputDevice<T> {
 }
 
 /// See: `deref`
-impl<T: ReadInputStream> DerefMut for InputDevice<T> {
+impl<T: ReadInputStream, R: Read> DerefMut for InputDevice<T, R> {
     fn deref_mut(&mut self) -> &mut Self::Target {
         &mut self.device
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Builds an event as the kernel writes it, with timestamp fields of `N` bytes.
+    fn raw_event<const N: usize>(sec: [u8; N], usec: [u8; N], r#type: u16, code: u16, value: i32) -> Vec<u8> {
+        [&sec[..], &usec, &r#type.to_ne_bytes(), &code.to_ne_bytes(), &value.to_ne_bytes()].concat()
+    }
+
+    #[test]
+    fn decodes_32_bit_events() {
+        let raw = raw_event(1_700_000_000u32.to_ne_bytes(), 250_000u32.to_ne_bytes(), 0x01, 0x14a, 1);
+        assert_eq!(raw.len(), 16);
+        assert_eq!(InputEvent::decode(&raw, 4), InputEvent {
+            time: Duration::new(1_700_000_000, 250_000_000),
+            r#type: 0x01,
+            code: 0x14a,
+            value: 1,
+        });
+    }
+
+    #[test]
+    fn decodes_64_bit_events() {
+        // Past 2038, which doesn't fit in 32 bits signed.
+        let raw = raw_event(3_000_000_000u64.to_ne_bytes(), 999_999u64.to_ne_bytes(), 0x03, 0x39, -1);
+        assert_eq!(raw.len(), 24);
+        assert_eq!(InputEvent::decode(&raw, 8), InputEvent {
+            time: Duration::new(3_000_000_000, 999_999_000),
+            r#type: 0x03,
+            code: 0x39,
+            value: -1,
+        });
+    }
+
+    /// Reads a byte stream a few bytes at a time, like a device whose events arrive cut off.
+    struct Chunked {
+        bytes: Vec<u8>,
+        chunks: Vec<usize>,
+    }
+
+    impl Read for Chunked {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            let len = if self.chunks.is_empty() { 0 } else { self.chunks.remove(0) }.min(self.bytes.len()).min(buf.len());
+            buf[..len].copy_from_slice(&self.bytes[..len]);
+            self.bytes.drain(..len);
+            Ok(len)
+        }
+    }
+
+    /// Keeps every event it's given, and fails on values of -1. Returns whether it got any.
+    #[derive(Default)]
+    struct Recorder(Vec<(u16, i32)>);
+
+    impl ReadInputStream for Recorder {
+        fn read_events(&mut self, stream: impl Iterator<Item = InputEvent>) -> Result<bool, Box<dyn Error>> {
+            let mut changed = false;
+            for event in stream {
+                if event.value == -1 {
+                    return Err("bad event".into());
+                }
+                self.0.push((event.code, event.value));
+                changed = true;
+            }
+            Ok(changed)
+        }
+    }
+
+    fn target_event(code: u16, value: i32) -> Vec<u8> {
+        vec![0u8; EVENT_SIZE - 8].into_iter().chain(raw_event([], [], 0x03, code, value)).collect()
+    }
+
+    #[test]
+    fn events_split_across_reads() {
+        let bytes = [target_event(1, 10), target_event(2, 20), target_event(3, 30)].concat();
+        // Cut the first event short, then end the second and start the third in one read.
+        let chunks = vec![5, EVENT_SIZE, EVENT_SIZE * 2 - 5];
+        let mut device = InputDevice::<Recorder, _>::from_reader(Chunked { bytes, chunks });
+
+        assert!(!device.poll().unwrap());
+        assert!(device.poll().unwrap());
+        assert_eq!(device.0, [(1, 10)]);
+        assert!(device.poll().unwrap());
+        assert_eq!(device.0, [(1, 10), (2, 20), (3, 30)]);
+        assert!(!device.poll().unwrap());
+    }
+
+    #[test]
+    fn errors_dont_replay_events() {
+        let bytes = [target_event(1, -1), target_event(2, 20)[..5].to_vec()].concat();
+        let mut device = InputDevice::<Recorder, _>::from_reader(Chunked { bytes: bytes.clone(), chunks: vec![bytes.len()] });
+        assert!(device.poll().is_err());
+
+        // The failed event is gone, and the cut off one still finishes.
+        device.file = Chunked { bytes: target_event(2, 20)[5..].to_vec(), chunks: vec![EVENT_SIZE] };
+        assert!(device.poll().unwrap());
+        assert_eq!(device.0, [(2, 20)]);
+    }
+
+    #[test]
+    fn decodes_target_events() {
+        let raw = vec![0u8; EVENT_SIZE - 8].into_iter().chain(raw_event([], [], 0x02, 0x08, -3)).collect::<Vec<_>>();
+        assert_eq!(InputEvent::from_bytes(&raw).value, -3);
+        assert_eq!(InputEvent::from_bytes(&raw).event(), Event::Rel(crate::input::event::Rel::WHEEL, -3));
+    }
+}
@@ -0,0 +1,377 @@
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::fs::{self, File};
+use std::os::raw::c_int;
+use std::os::unix::io::AsRawFd;
+use std::path::{Path, PathBuf};
+use crate::input::device::{InputDevice, ReadInputStream};
+use crate::input::event::{Abs, Key, Rel, EV_ABS, EV_KEY, EV_REL};
+
+/// glibc takes an `unsigned long` request, musl an `int`.
+#[cfg(target_env = "musl")]
+type IoctlRequest = c_int;
+#[cfg(not(target_env = "musl"))]
+type IoctlRequest = std::os::raw::c_ulong;
+
+extern "C" {
+    fn ioctl(fd: c_int, request: IoctlRequest, ...) -> c_int;
+}
+
+/// Builds the request number of a read-only evdev ioctl, like the kernel's `_IOR('E', nr, len)`.
+const fn evdev_read(nr: u32, len: usize) -> IoctlRequest {
+    const READ: u32 = 2;
+    ((READ << 30) | ((len as u32) << 16) | ((b'E' as u32) << 8) | nr) as IoctlRequest
+}
+
+/// `EVIOCGNAME`, the device's name.
+const EVIOCGNAME: u32 = 0x06;
+/// `EVIOCGPROP`, the device's properties.
+const EVIOCGPROP: u32 = 0x09;
+/// `EVIOCGBIT`, the event types a device sends (0), or the codes of one event type it sends.
+const EVIOCGBIT: u32 = 0x20;
+
+/// `INPUT_PROP_DIRECT`: the device is drawn on directly, like a touchscreen.
+const INPUT_PROP_DIRECT: usize = 0x01;
+/// `BTN_JOYSTICK`, the first joystick button.
+const BTN_JOYSTICK: Key = Key(0x120);
+
+/// Highest code of each kind of bitmap, plus one, from `linux/input-event-codes.h`.
+const PROP_CNT: usize = 0x20;
+const EV_CNT: usize = 0x20;
+const KEY_CNT: usize = 0x300;
+const REL_CNT: usize = 0x10;
+const ABS_CNT: usize = 0x40;
+
+/// What kind of device an input device looks like, going by the events it can send.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum DeviceKind {
+    Touchscreen,
+    Keyboard,
+    /// Mice, and touchpads that move a pointer.
+    Mouse,
+    Gamepad,
+    Other,
+}
+
+/// A set of bits, as the kernel reports which events and codes a device supports.
+#[derive(Clone, Default, PartialEq, Eq, Debug)]
+pub struct Bits(Vec<usize>);
+
+impl Bits {
+    pub fn has(&self, bit: usize) -> bool {
+        self.0.get(bit / usize::BITS as usize).is_some_and(|word| word >> (bit % usize::BITS as usize) & 1 == 1)
+    }
+
+    /// Parses a bitmap as printed by the kernel, in hex words, most significant first.
+    fn parse(text: &str) -> Option<Self> {
+        text.split_whitespace().rev().map(|word| usize::from_str_radix(word, 16).ok()).collect::<Option<_>>().map(Bits)
+    }
+}
+
+/// Which events and codes a device can send.
+#[derive(Clone, Default, PartialEq, Eq, Debug)]
+pub struct Capabilities {
+    /// Properties, such as `INPUT_PROP_DIRECT`.
+    pub props: Bits,
+    /// Event types, such as `EV_KEY`.
+    pub events: Bits,
+    pub keys: Bits,
+    pub rel: Bits,
+    pub abs: Bits,
+}
+
+impl Capabilities {
+    pub fn has_key(&self, key: Key) -> bool {
+        self.events.has(EV_KEY as usize) && self.keys.has(key.0 as usize)
+    }
+
+    pub fn has_rel(&self, rel: Rel) -> bool {
+        self.events.has(EV_REL as usize) && self.rel.has(rel.0 as usize)
+    }
+
+    pub fn has_abs(&self, abs: Abs) -> bool {
+        self.events.has(EV_ABS as usize) && self.abs.has(abs.0 as usize)
+    }
+
+    /// Guesses what kind of device this is, much like udev's `input_id`. A touchscreen is an
+    /// absolute pointer drawn on directly, a gamepad has gamepad or joystick buttons, a mouse
+    /// moves a pointer, and a keyboard has letter keys.
+    pub fn kind(&self) -> DeviceKind {
+        let position = (self.has_abs(Abs::X) && self.has_abs(Abs::Y))
+            || (self.has_abs(Abs::MT_POSITION_X) && self.has_abs(Abs::MT_POSITION_Y));
+        let direct = self.props.has(INPUT_PROP_DIRECT)
+            || (self.has_key(Key::BTN_TOUCH) && !self.has_key(Key::BTN_TOOL_FINGER) && !self.has_key(Key::BTN_TOOL_PEN));
+
+        if position && direct {
+            DeviceKind::Touchscreen
+        } else if self.has_key(Key::BTN_SOUTH) || self.has_key(BTN_JOYSTICK) {
+            DeviceKind::Gamepad
+        } else if (self.has_rel(Rel::X) && self.has_rel(Rel::Y) && self.has_key(Key::BTN_LEFT))
+            || (position && self.has_key(Key::BTN_TOOL_FINGER)) {
+            DeviceKind::Mouse
+        } else if [Key::Q, Key::A, Key::Z, Key::SPACE, Key::ENTER].into_iter().all(|key| self.has_key(key)) {
+            DeviceKind::Keyboard
+        } else {
+            DeviceKind::Other
+        }
+    }
+}
+
+/// An input device found by `scan`.
+#[derive(Clone, Debug)]
+pub struct DeviceInfo {
+    pub name: String,
+    /// The device node to read events from, such as `/dev/input/event3`.
+    pub path: PathBuf,
+    pub kind: DeviceKind,
+    pub capabilities: Capabilities,
+}
+
+impl DeviceInfo {
+    /// Opens the device to read events as `T`, such as `Touchscreen`.
+    pub fn open<T: ReadInputStream + Default>(&self) -> Result<InputDevice<T>, Box<dyn Error>> {
+        let file = File::open(&self.path).map_err(|e| format!("Couldn't open {}: {}", self.path.display(), e))?;
+        Ok(InputDevice::new(file))
+    }
+}
+
+/// Finds every input device under `root`, which is `/` except when testing against a fake tree.
+/// Each `dev/input/event*` node is asked for its name and capabilities. Devices that can't be
+/// asked, such as ones the user isn't allowed to open, are looked up in
+/// `proc/bus/input/devices` instead. Devices listed there without a node, which couldn't be opened
+/// anyway, are left out. Devices are sorted by event number.
+pub fn scan(root: &Path) -> Vec<DeviceInfo> {
+    let mut listed = fs::read_to_string(root.join("proc/bus/input/devices"))
+        .map(|text| parse_proc_devices(&text))
+        .unwrap_or_default();
+
+    let nodes: BTreeMap<usize, String> = fs::read_dir(root.join("dev/input"))
+        .into_iter()
+        .flatten()
+        .flatten()
+        .filter_map(|entry| entry.file_name().into_string().ok())
+        .filter_map(|node| Some((node.strip_prefix("event")?.parse().ok()?, node)))
+        .collect();
+
+    let input = root.join("dev/input");
+    let mut devices = Vec::new();
+    for node in nodes.into_values() {
+        let path = input.join(&node);
+        let found = query(&path).or_else(|| listed.remove(&node));
+        if let Some((name, capabilities)) = found {
+            devices.push(DeviceInfo { name, path, kind: capabilities.kind(), capabilities });
+        }
+    }
+    devices
+}
+
+/// Finds the first device of a kind under `root`.
+pub fn find(root: &Path, kind: DeviceKind) -> Option<DeviceInfo> {
+    scan(root).into_iter().find(|device| device.kind == kind)
+}
+
+/// Finds the touchscreen hidden amongst all input devices.
+pub fn find_touchscreen() -> Option<DeviceInfo> {
+    find(Path::new("/"), DeviceKind::Touchscreen)
+}
+
+/// Asks a device node for its name and capabilities with evdev ioctls. Returns `None` if it can't
+/// be opened or isn't an evdev device.
+fn query(path: &Path) -> Option<(String, Capabilities)> {
+    let file = File::open(path).ok()?;
+    let fd = file.as_raw_fd();
+
+    let mut name = [0u8; 256];
+    // SAFETY: The kernel writes at most the buffer's length, which is encoded in the request.
+    let len = unsafe { ioctl(fd, evdev_read(EVIOCGNAME, name.len()), name.as_mut_ptr()) };
+    if len < 0 {
+        return None;
+    }
+    let name = String::from_utf8_lossy(&name[..len as usize]).trim_end_matches('\0').to_owned();
+
+    let bits = |nr: u32, count: usize| {
+        let mut words = vec![0usize; count.div_ceil(usize::BITS as usize)];
+        let len = std::mem::size_of_val(words.as_slice());
+        // SAFETY: As above, the kernel writes at most `len` bytes.
+        let read = unsafe { ioctl(fd, evdev_read(nr, len), words.as_mut_ptr()) };
+        (read >= 0).then_some(Bits(words))
+    };
+
+    let capabilities = Capabilities {
+        // Older kernels don't have properties.
+        props: bits(EVIOCGPROP, PROP_CNT).unwrap_or_default(),
+        events: bits(EVIOCGBIT, EV_CNT)?,
+        keys: bits(EVIOCGBIT + EV_KEY as u32, KEY_CNT)?,
+        rel: bits(EVIOCGBIT + EV_REL as u32, REL_CNT)?,
+        abs: bits(EVIOCGBIT + EV_ABS as u32, ABS_CNT)?,
+    };
+    Some((name, capabilities))
+}
+
+/// Parses `/proc/bus/input/devices` into the name and capabilities of each device, by the name of
+/// its event node, such as `event3`. Bitmaps are assumed to be in words the size of the target's
+/// `usize`, as the kernel's `unsigned long` is unless a 32-bit program runs on a 64-bit kernel.
+fn parse_proc_devices(text: &str) -> BTreeMap<String, (String, Capabilities)> {
+    let mut devices = BTreeMap::new();
+    for block in text.split("\n\n") {
+        let mut name = String::new();
+        let mut node = None;
+        let mut capabilities = Capabilities::default();
+
+        for line in block.lines() {
+            let (tag, rest) = match line.split_once(": ") {
+                Some(parts) => parts,
+                None => continue,
+            };
+            match tag {
+                "N" => name = rest.trim_start_matches("Name=").trim_matches('"').to_owned(),
+                "H" => node = rest.trim_start_matches("Handlers=").split_whitespace().find(|h| h.starts_with("event")),
+                "B" => {
+                    let (bitmap, value) = match rest.split_once('=') {
+                        Some(parts) => parts,
+                        None => continue,
+                    };
+                    let bits = Bits::parse(value).unwrap_or_default();
+                    match bitmap {
+                        "PROP" => capabilities.props = bits,
+                        "EV" => capabilities.events = bits,
+                        "KEY" => capabilities.keys = bits,
+                        "REL" => capabilities.rel = bits,
+                        "ABS" => capabilities.abs = bits,
+                        _ => {}
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        if let Some(node) = node {
+            devices.insert(node.to_owned(), (name, capabilities));
+        }
+    }
+    devices
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Prints bits the way the kernel does in `/proc/bus/input/devices`.
+    fn bitmap(bits: &[u16]) -> String {
+        let word_bits = usize::BITS as usize;
+        let mut words = vec![0usize; bits.iter().map(|&bit| bit as usize / word_bits + 1).max().unwrap_or(1)];
+        for &bit in bits {
+            words[bit as usize / word_bits] |= 1 << (bit as usize % word_bits);
+        }
+        words.iter().rev().map(|word| format!("{:x}", word)).collect::<Vec<_>>().join(" ")
+    }
+
+    fn entry(name: &str, handlers: &str, props: &[u16], events: &[u16], keys: &[Key], rel: &[Rel], abs: &[Abs]) -> String {
+        let keys = keys.iter().map(|key| key.0).collect::<Vec<_>>();
+        let rel = rel.iter().map(|rel| rel.0).collect::<Vec<_>>();
+        let abs = abs.iter().map(|abs| abs.0).collect::<Vec<_>>();
+        format!(
+            "I: Bus=0018 Vendor=0000 Product=0000 Version=0000\nN: Name=\"{}\"\nP: Phys=\nH: Handlers={}\n\
+             B: PROP={}\nB: EV={}\nB: KEY={}\nB: REL={}\nB: ABS={}\n",
+            name, handlers, bitmap(props), bitmap(events), bitmap(&keys), bitmap(&rel), bitmap(&abs),
+        )
+    }
+
+    fn devices() -> String {
+        [
+            entry("Goodix Capacitive TouchScreen", "kbd event0", &[INPUT_PROP_DIRECT as u16], &[EV_KEY, EV_ABS],
+                &[Key::BTN_TOUCH], &[], &[Abs::X, Abs::Y, Abs::MT_SLOT, Abs::MT_POSITION_X, Abs::MT_POSITION_Y, Abs::MT_TRACKING_ID]),
+            entry("AT Translated Set 2 keyboard", "sysrq kbd leds event1", &[], &[EV_KEY],
+                &[Key::ESC, Key::Q, Key::A, Key::Z, Key::SPACE, Key::ENTER], &[], &[]),
+            entry("USB Optical Mouse", "mouse0 event2", &[], &[EV_KEY, EV_REL],
+                &[Key::BTN_LEFT, Key::BTN_RIGHT], &[Rel::X, Rel::Y, Rel::WHEEL], &[]),
+            entry("SynPS/2 Synaptics TouchPad", "mouse1 event3", &[], &[EV_KEY, EV_ABS],
+                &[Key::BTN_LEFT, Key::BTN_TOOL_FINGER, Key::BTN_TOUCH], &[], &[Abs::X, Abs::Y]),
+            entry("Xbox Wireless Controller", "js0 event10", &[], &[EV_KEY, EV_ABS],
+                &[Key::BTN_SOUTH, Key::BTN_EAST, Key::BTN_START], &[], &[Abs::X, Abs::Y, Abs::HAT0X]),
+            entry("Power Button", "kbd event4", &[], &[EV_KEY], &[Key::POWER], &[], &[]),
+        ]
+        .join("\n")
+    }
+
+    /// A fake root with `proc/bus/input/devices` and empty files for event nodes, which can't be
+    /// queried, so devices are looked up in procfs. Removed when dropped.
+    struct Root(PathBuf);
+
+    impl Root {
+        fn new(name: &str, devices: &str, nodes: &[&str]) -> Self {
+            let root = std::env::temp_dir().join(format!("discovery-{}-{}", name, std::process::id()));
+            fs::create_dir_all(root.join("proc/bus/input")).unwrap();
+            fs::create_dir_all(root.join("dev/input")).unwrap();
+            fs::write(root.join("proc/bus/input/devices"), devices).unwrap();
+            for node in nodes {
+                fs::write(root.join("dev/input").join(node), []).unwrap();
+            }
+            Root(root)
+        }
+    }
+
+    impl Drop for Root {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    #[test]
+    fn parses_bits() {
+        let bits = Bits::parse("10 1").unwrap();
+        assert!(bits.has(0));
+        assert!(!bits.has(1));
+        assert!(bits.has(usize::BITS as usize + 4));
+        assert!(!bits.has(usize::BITS as usize * 2));
+        assert_eq!(Bits::parse(""), Some(Bits(Vec::new())));
+        assert_eq!(Bits::parse("1 zz"), None);
+    }
+
+    #[test]
+    fn parses_proc_devices() {
+        let devices = parse_proc_devices(&devices());
+        assert_eq!(devices.keys().map(String::as_str).collect::<Vec<_>>(), ["event0", "event1", "event10", "event2", "event3", "event4"]);
+
+        let (name, capabilities) = &devices["event0"];
+        assert_eq!(name, "Goodix Capacitive TouchScreen");
+        assert!(capabilities.props.has(INPUT_PROP_DIRECT));
+        assert!(capabilities.has_key(Key::BTN_TOUCH));
+        assert!(capabilities.has_abs(Abs::MT_TRACKING_ID));
+        assert!(!capabilities.has_abs(Abs::PRESSURE));
+        assert!(!capabilities.has_rel(Rel::X));
+    }
+
+    #[test]
+    fn guesses_kinds() {
+        let devices = parse_proc_devices(&devices());
+        let kind = |node: &str| devices[node].1.kind();
+        assert_eq!(kind("event0"), DeviceKind::Touchscreen);
+        assert_eq!(kind("event1"), DeviceKind::Keyboard);
+        assert_eq!(kind("event2"), DeviceKind::Mouse);
+        assert_eq!(kind("event3"), DeviceKind::Mouse);
+        assert_eq!(kind("event10"), DeviceKind::Gamepad);
+        assert_eq!(kind("event4"), DeviceKind::Other);
+    }
+
+    #[test]
+    fn scans_a_fake_root() {
+        // event5 has no procfs entry, and event3 is listed but has no node to open.
+        let root = Root::new("scan", &devices(), &["event0", "event1", "event2", "event4", "event5", "event10", "mouse0"]);
+        let found = scan(&root.0);
+
+        let nodes = found.iter().map(|device| device.path.strip_prefix(&root.0).unwrap().to_str().unwrap()).collect::<Vec<_>>();
+        assert_eq!(nodes, ["dev/input/event0", "dev/input/event1", "dev/input/event2", "dev/input/event4", "dev/input/event10"]);
+        assert!(found.iter().all(|device| device.path.exists()));
+        assert_eq!(found[2].name, "USB Optical Mouse");
+
+        let touchscreen = find(&root.0, DeviceKind::Touchscreen).unwrap();
+        assert_eq!(touchscreen.path, root.0.join("dev/input/event0"));
+        assert!(find(&Root::new("empty", "", &[]).0, DeviceKind::Keyboard).is_none());
+
+        // A touchscreen without a node isn't handed out to be opened.
+        let missing = Root::new("missing", &devices(), &["event1"]);
+        assert!(find(&missing.0, DeviceKind::Touchscreen).is_none());
+        assert!(find(&missing.0, DeviceKind::Keyboard).is_some());
+    }
+}
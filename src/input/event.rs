@@ -0,0 +1,227 @@
+use crate::input::device::InputEvent;
+
+// Event types, from `linux/input-event-codes.h`.
+pub const EV_SYN: u16 = 0x00;
+pub const EV_KEY: u16 = 0x01;
+pub const EV_REL: u16 = 0x02;
+pub const EV_ABS: u16 = 0x03;
+pub const EV_MSC: u16 = 0x04;
+
+/// An evdev event decoded by its type. Codes are kept as numbers wrapped in a type per event type,
+/// with the codes this crate knows about as named constants, so unnamed codes still come through.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Event {
+    /// Marks the end of a group of events that happened at the same time.
+    Syn(Syn),
+    /// A key or button changed state.
+    Key(Key, KeyState),
+    /// A relative axis moved by the value, such as a mouse or scroll wheel.
+    Rel(Rel, i32),
+    /// An absolute axis changed to the value, such as a touch position.
+    Abs(Abs, i32),
+    /// Miscellaneous data, such as a scancode that goes with a key event.
+    Msc(Msc, i32),
+    /// Any other type of event, such as switches and LEDs.
+    Other(InputEvent),
+}
+
+impl From<&InputEvent> for Event {
+    fn from(event: &InputEvent) -> Self {
+        match event.r#type {
+            EV_SYN => Event::Syn(Syn(event.code)),
+            EV_KEY => Event::Key(Key(event.code), KeyState::from(event.value)),
+            EV_REL => Event::Rel(Rel(event.code), event.value),
+            EV_ABS => Event::Abs(Abs(event.code), event.value),
+            EV_MSC => Event::Msc(Msc(event.code), event.value),
+            _ => Event::Other(*event),
+        }
+    }
+}
+
+/// Codes of `EV_SYN` events.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct Syn(pub u16);
+
+impl Syn {
+    /// Every event since the last report happened at once, and can be acted on.
+    pub const REPORT: Syn = Syn(0x00);
+    pub const CONFIG: Syn = Syn(0x01);
+    /// Ends one contact of the old, slotless multi-touch protocol A.
+    pub const MT_REPORT: Syn = Syn(0x02);
+    /// The kernel's buffer overflowed and events were lost. Everything up to the next report
+    /// should be ignored, and device state queried again.
+    pub const DROPPED: Syn = Syn(0x03);
+}
+
+/// Whether a key or button went down, came up, or is auto-repeating.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum KeyState {
+    Released,
+    Pressed,
+    Repeated,
+}
+
+impl From<i32> for KeyState {
+    fn from(value: i32) -> Self {
+        match value {
+            0 => KeyState::Released,
+            1 => KeyState::Pressed,
+            _ => KeyState::Repeated,
+        }
+    }
+}
+
+/// Codes of `EV_KEY` events, for both keys (`KEY_*`) and buttons (`BTN_*`).
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct Key(pub u16);
+
+impl Key {
+    pub const ESC: Key = Key(1);
+    pub const NUM_1: Key = Key(2);
+    pub const NUM_2: Key = Key(3);
+    pub const NUM_3: Key = Key(4);
+    pub const NUM_4: Key = Key(5);
+    pub const NUM_5: Key = Key(6);
+    pub const NUM_6: Key = Key(7);
+    pub const NUM_7: Key = Key(8);
+    pub const NUM_8: Key = Key(9);
+    pub const NUM_9: Key = Key(10);
+    pub const NUM_0: Key = Key(11);
+    pub const BACKSPACE: Key = Key(14);
+    pub const TAB: Key = Key(15);
+    pub const Q: Key = Key(16);
+    pub const W: Key = Key(17);
+    pub const E: Key = Key(18);
+    pub const R: Key = Key(19);
+    pub const T: Key = Key(20);
+    pub const Y: Key = Key(21);
+    pub const U: Key = Key(22);
+    pub const I: Key = Key(23);
+    pub const O: Key = Key(24);
+    pub const P: Key = Key(25);
+    pub const ENTER: Key = Key(28);
+    pub const LEFT_CTRL: Key = Key(29);
+    pub const A: Key = Key(30);
+    pub const S: Key = Key(31);
+    pub const D: Key = Key(32);
+    pub const F: Key = Key(33);
+    pub const G: Key = Key(34);
+    pub const H: Key = Key(35);
+    pub const J: Key = Key(36);
+    pub const K: Key = Key(37);
+    pub const L: Key = Key(38);
+    pub const LEFT_SHIFT: Key = Key(42);
+    pub const Z: Key = Key(44);
+    pub const X: Key = Key(45);
+    pub const C: Key = Key(46);
+    pub const V: Key = Key(47);
+    pub const B: Key = Key(48);
+    pub const N: Key = Key(49);
+    pub const M: Key = Key(50);
+    pub const RIGHT_SHIFT: Key = Key(54);
+    pub const LEFT_ALT: Key = Key(56);
+    pub const SPACE: Key = Key(57);
+    pub const RIGHT_CTRL: Key = Key(97);
+    pub const RIGHT_ALT: Key = Key(100);
+    pub const HOME: Key = Key(102);
+    pub const UP: Key = Key(103);
+    pub const PAGE_UP: Key = Key(104);
+    pub const LEFT: Key = Key(105);
+    pub const RIGHT: Key = Key(106);
+    pub const END: Key = Key(107);
+    pub const DOWN: Key = Key(108);
+    pub const PAGE_DOWN: Key = Key(109);
+    pub const DELETE: Key = Key(111);
+    pub const POWER: Key = Key(116);
+
+    pub const BTN_LEFT: Key = Key(0x110);
+    pub const BTN_RIGHT: Key = Key(0x111);
+    pub const BTN_MIDDLE: Key = Key(0x112);
+
+    pub const BTN_SOUTH: Key = Key(0x130);
+    pub const BTN_EAST: Key = Key(0x131);
+    pub const BTN_NORTH: Key = Key(0x133);
+    pub const BTN_WEST: Key = Key(0x134);
+    pub const BTN_TL: Key = Key(0x136);
+    pub const BTN_TR: Key = Key(0x137);
+    pub const BTN_SELECT: Key = Key(0x13a);
+    pub const BTN_START: Key = Key(0x13b);
+    pub const BTN_MODE: Key = Key(0x13c);
+
+    pub const BTN_TOOL_PEN: Key = Key(0x140);
+    pub const BTN_TOOL_FINGER: Key = Key(0x145);
+    /// Something is touching the surface of a touchscreen or touchpad.
+    pub const BTN_TOUCH: Key = Key(0x14a);
+    pub const BTN_TOOL_DOUBLETAP: Key = Key(0x14d);
+    pub const BTN_TOOL_TRIPLETAP: Key = Key(0x14e);
+}
+
+/// Codes of `EV_REL` events.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct Rel(pub u16);
+
+impl Rel {
+    pub const X: Rel = Rel(0x00);
+    pub const Y: Rel = Rel(0x01);
+    pub const Z: Rel = Rel(0x02);
+    pub const RX: Rel = Rel(0x03);
+    pub const RY: Rel = Rel(0x04);
+    pub const RZ: Rel = Rel(0x05);
+    pub const HWHEEL: Rel = Rel(0x06);
+    pub const DIAL: Rel = Rel(0x07);
+    pub const WHEEL: Rel = Rel(0x08);
+    pub const MISC: Rel = Rel(0x09);
+    pub const WHEEL_HI_RES: Rel = Rel(0x0b);
+    pub const HWHEEL_HI_RES: Rel = Rel(0x0c);
+}
+
+/// Codes of `EV_ABS` events.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct Abs(pub u16);
+
+impl Abs {
+    pub const X: Abs = Abs(0x00);
+    pub const Y: Abs = Abs(0x01);
+    pub const Z: Abs = Abs(0x02);
+    pub const RX: Abs = Abs(0x03);
+    pub const RY: Abs = Abs(0x04);
+    pub const RZ: Abs = Abs(0x05);
+    pub const THROTTLE: Abs = Abs(0x06);
+    pub const RUDDER: Abs = Abs(0x07);
+    pub const WHEEL: Abs = Abs(0x08);
+    pub const GAS: Abs = Abs(0x09);
+    pub const BRAKE: Abs = Abs(0x0a);
+    pub const HAT0X: Abs = Abs(0x10);
+    pub const HAT0Y: Abs = Abs(0x11);
+    pub const PRESSURE: Abs = Abs(0x18);
+    pub const DISTANCE: Abs = Abs(0x19);
+
+    /// Selects which contact the multi-touch events that follow are about.
+    pub const MT_SLOT: Abs = Abs(0x2f);
+    pub const MT_TOUCH_MAJOR: Abs = Abs(0x30);
+    pub const MT_TOUCH_MINOR: Abs = Abs(0x31);
+    pub const MT_WIDTH_MAJOR: Abs = Abs(0x32);
+    pub const MT_WIDTH_MINOR: Abs = Abs(0x33);
+    pub const MT_ORIENTATION: Abs = Abs(0x34);
+    pub const MT_POSITION_X: Abs = Abs(0x35);
+    pub const MT_POSITION_Y: Abs = Abs(0x36);
+    pub const MT_TOOL_TYPE: Abs = Abs(0x37);
+    pub const MT_BLOB_ID: Abs = Abs(0x38);
+    /// Starts a contact in the current slot with a new id, or ends it with -1.
+    pub const MT_TRACKING_ID: Abs = Abs(0x39);
+    pub const MT_PRESSURE: Abs = Abs(0x3a);
+    pub const MT_DISTANCE: Abs = Abs(0x3b);
+}
+
+/// Codes of `EV_MSC` events.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct Msc(pub u16);
+
+impl Msc {
+    pub const SERIAL: Msc = Msc(0x00);
+    pub const PULSELED: Msc = Msc(0x01);
+    pub const GESTURE: Msc = Msc(0x02);
+    pub const RAW: Msc = Msc(0x03);
+    pub const SCAN: Msc = Msc(0x04);
+    pub const TIMESTAMP: Msc = Msc(0x05);
+}
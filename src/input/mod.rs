@@ -1,2 +1,4 @@
 pub mod device;
+pub mod discovery;
+pub mod event;
 pub mod touchscreen;
\ No newline at end of file
@@ -1,79 +1,314 @@
-use std::collections::VecDeque;
+use std::collections::BTreeMap;
 use std::error::Error;
-use crate::{ABSOLUTE_X_POS, ABSOLUTE_Y_POS, EV_KEY, EV_SYN, TOUCHES_BEGAN, TOUCHES_ENDED};
 use crate::input::device::{InputEvent, ReadInputStream};
+use crate::input::event::{Abs, Event, Key, KeyState, Syn};
 
-/// Represents a touchscreen interface.
+/// Where a contact is in its life, as of the last call to `Touchscreen::touches`.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum TouchPhase {
+    /// The finger went down since the last call.
+    Began,
+    /// The finger was already down, and may have moved.
+    Moved,
+    /// The finger was lifted since the last call. Its id won't be reported again.
+    Ended,
+}
+
+/// One finger on the screen, and where it went since the last call to `Touchscreen::touches`.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Touch {
+    /// Stays the same from when the finger goes down until it is lifted, even while other fingers
+    /// come and go. Each new contact gets a higher id than the last, so ids are never reused.
+    pub id: i32,
+    pub phase: TouchPhase,
+    /// Points the finger moved through, oldest first. Empty if it didn't move.
+    pub points: Vec<(usize, usize)>,
+}
+
+/// What the kernel last said about one multi-touch slot. Values are only sent when they change,
+/// so they are kept even after the contact ends.
+#[derive(Clone, Copy, Default)]
+struct Slot {
+    /// Id of the contact in the slot, or `None` if the slot is empty. This is our own id, not the
+    /// kernel's tracking id.
+    id: Option<i32>,
+    x: Option<usize>,
+    y: Option<usize>,
+    /// Whether the position changed since the last report.
+    moved: bool,
+    /// Set when events were dropped, since the slot may still have a contact whose tracking id
+    /// was lost. A position for the slot begins a new contact.
+    lost: bool,
+}
+
+/// Represents a touchscreen interface. Follows the multi-touch protocol B, where each finger has
+/// a slot and a tracking id, so several fingers can draw at once. Screens without multi-touch
+/// are read as a single contact.
 #[derive(Default)]
 pub struct Touchscreen {
-    /// A ring buffer for the current touch's points. Acts as a stack where the first position is
-    /// the most recently touched. Using a `VecDeque` lets us iterate freely in both directions and
-    /// is space efficient.
-    trail: VecDeque<(usize, usize)>,
-    /// Since each event only comes with one part of the coordinate pair, we need to buffer one
-    /// until the other is received. When both values are `Some`, we push to the trail and reset
-    /// these values to None.
-    next_x: Option<usize>,
-    /// See: `next_x`.
-    next_y: Option<usize>,
-    /// The delta holds the coordinate vector difference from the origin of the last touch trail.
-    /// It should technically be the sum of the entire `trail`. This field in particular is useful
-    /// for implementing touch-and-drag interfaces where the element is offset by the touch offset.
-    delta: (usize, usize),
-    /// Indicates to the user that the user has lifted their finger, and that they should stop
-    /// dragging, process a tap, or stop connecting lines.
-    touches_ended: bool,
+    /// Contacts by slot number.
+    slots: Vec<Slot>,
+    /// The slot that multi-touch events are about, chosen with `ABS_MT_SLOT`.
+    slot: usize,
+    /// Whether the device has sent any multi-touch events. Until then, the single-touch `ABS_X`,
+    /// `ABS_Y` and `BTN_TOUCH` events are used instead. Multi-touch screens send those too, for
+    /// the first finger only.
+    multitouch: bool,
+    /// Set when the kernel dropped events. Everything up to the next report is ignored, and the
+    /// contacts are rebuilt from the reports after it.
+    dropped: bool,
+    /// Contacts with anything to report since the last call to `touches`, by id.
+    touches: BTreeMap<i32, Touch>,
+    /// Id of the next contact to begin.
+    next_id: i32,
 }
 
 impl ReadInputStream for Touchscreen {
     fn read_events(&mut self, stream: impl Iterator<Item = InputEvent>) -> Result<bool, Box<dyn Error>> {
+        let mut changed = false;
         for event in stream {
-            match (event.r#type, event.code) {
-                (EV_SYN, _,) => {
-                    if let (Some(x), Some(y)) = (self.next_x, self.next_y) {
-                        self.trail.push_front((x, y));
-                        self.next_x = None;
-                        self.next_y = None;
+            let event = event.event();
+            if self.dropped {
+                // Positions may be stale, but the next report brings the ones that change.
+                self.dropped = event != Event::Syn(Syn::REPORT);
+                continue;
+            }
+
+            match event {
+                Event::Syn(Syn::REPORT) => changed |= self.report(),
+                Event::Syn(Syn::DROPPED) => self.drop_contacts(),
+
+                Event::Abs(Abs::MT_SLOT, value) => {
+                    self.multitouch = true;
+                    self.slot = value.max(0) as usize;
+                }
+                Event::Abs(Abs::MT_TRACKING_ID, value) => {
+                    self.multitouch = true;
+                    let slot = self.slot;
+                    // A new id in a slot that still has a contact replaces it.
+                    self.end(slot);
+                    self.slot_mut(slot).lost = false;
+                    if value >= 0 {
+                        self.begin(slot);
                     }
                 }
-                (EV_KEY, ABSOLUTE_X_POS) => self.next_x = Some(event.value as usize),
-                (EV_KEY, ABSOLUTE_Y_POS) => self.next_y = Some(event.value as usize),
-                (EV_KEY, TOUCHES_BEGAN) => self.touches_ended = false,
-                (EV_KEY, TOUCHES_ENDED) => self.touches_ended = true,
+                Event::Abs(Abs::MT_POSITION_X, value) => {
+                    self.multitouch = true;
+                    self.move_to(self.slot, Some(value.max(0) as usize), None);
+                }
+                Event::Abs(Abs::MT_POSITION_Y, value) => {
+                    self.multitouch = true;
+                    self.move_to(self.slot, None, Some(value.max(0) as usize));
+                }
+
+                Event::Abs(Abs::X, value) if !self.multitouch => self.move_to(0, Some(value.max(0) as usize), None),
+                Event::Abs(Abs::Y, value) if !self.multitouch => self.move_to(0, None, Some(value.max(0) as usize)),
+                Event::Key(Key::BTN_TOUCH, KeyState::Pressed) if !self.multitouch && self.slot_mut(0).id.is_none() => self.begin(0),
+                Event::Key(Key::BTN_TOUCH, KeyState::Released) if !self.multitouch => self.end(0),
                 _ => {}
             }
         }
 
-        // We assume if this was called, we can signal the user to process events. However, we
-        // really shouldn't if every event in `stream` fell into the default match case.
-        Ok(true)
+        Ok(changed)
     }
 }
 
-/// Represents a single-touch touchscreen device.
 impl Touchscreen {
-    /// Returns `Some((x, y))` if the user clicked/tapped on the screen.
-    fn get_tap(&mut self) -> Option<(usize, usize)> {
-        match self.touches_ended {
-            false => None,
-            true => {
-                // ensure the `trail` has not deviated by more than a few (10?) pixels
-                None
+    /// Returns every contact that is down, or was lifted since the last call, ordered by id. A
+    /// finger that went down and up again in between is reported once, as `Ended`.
+    pub fn touches(&mut self) -> Vec<Touch> {
+        let touches = std::mem::take(&mut self.touches);
+
+        // Contacts still down carry on as moved.
+        for touch in touches.values().filter(|touch| touch.phase != TouchPhase::Ended) {
+            self.touches.insert(touch.id, Touch { id: touch.id, phase: TouchPhase::Moved, points: Vec::new() });
+        }
+        touches.into_values().collect()
+    }
+
+    fn slot_mut(&mut self, slot: usize) -> &mut Slot {
+        if slot >= self.slots.len() {
+            self.slots.resize(slot + 1, Slot::default());
+        }
+        &mut self.slots[slot]
+    }
+
+    /// Starts a contact in a slot with a new id. The slot keeps its last position, since the kernel
+    /// doesn't send values that are the same as the last contact's.
+    fn begin(&mut self, slot: usize) {
+        let id = self.next_id;
+        self.next_id = self.next_id.wrapping_add(1);
+        let slot = self.slot_mut(slot);
+        slot.id = Some(id);
+        slot.lost = false;
+        slot.moved = true;
+        self.touches.insert(id, Touch { id, phase: TouchPhase::Began, points: Vec::new() });
+    }
+
+    fn end(&mut self, slot: usize) {
+        if let Some(id) = self.slot_mut(slot).id.take() {
+            self.touches.entry(id).or_insert_with(|| Touch { id, phase: TouchPhase::Moved, points: Vec::new() }).phase = TouchPhase::Ended;
+        }
+    }
+
+    /// Ends every contact, since lifts may have been among the dropped events, and marks every slot
+    /// as lost until it's heard from again.
+    fn drop_contacts(&mut self) {
+        self.dropped = true;
+        for slot in 0..self.slots.len() {
+            self.end(slot);
+            self.slots[slot].lost = true;
+        }
+    }
+
+    fn move_to(&mut self, slot_index: usize, x: Option<usize>, y: Option<usize>) {
+        let slot = self.slot_mut(slot_index);
+        if slot.lost {
+            // The finger stayed down through the dropped events.
+            self.begin(slot_index);
+        }
+        let slot = self.slot_mut(slot_index);
+        slot.x = x.or(slot.x);
+        slot.y = y.or(slot.y);
+        slot.moved = true;
+    }
+
+    /// Adds the position of every contact that moved to its points. Returns whether there's
+    /// anything new to report.
+    fn report(&mut self) -> bool {
+        for slot in self.slots.iter_mut().filter(|slot| slot.moved) {
+            slot.moved = false;
+            if let (Some(id), Some(x), Some(y)) = (slot.id, slot.x, slot.y) {
+                self.touches.entry(id).or_insert_with(|| Touch { id, phase: TouchPhase::Moved, points: Vec::new() }).points.push((x, y));
             }
         }
+        self.touches.values().any(|touch| touch.phase != TouchPhase::Moved || !touch.points.is_empty())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+    use crate::input::event::{EV_ABS, EV_KEY, EV_SYN};
+
+    fn event(r#type: u16, code: u16, value: i32) -> InputEvent {
+        InputEvent { time: Duration::ZERO, r#type, code, value }
+    }
+
+    fn abs(abs: Abs, value: i32) -> InputEvent {
+        event(EV_ABS, abs.0, value)
+    }
+
+    fn report() -> InputEvent {
+        event(EV_SYN, Syn::REPORT.0, 0)
+    }
+
+    /// A touch as id, phase and points, to compare with less typing.
+    type Reported = (i32, TouchPhase, Vec<(usize, usize)>);
+
+    fn read(touchscreen: &mut Touchscreen, events: &[InputEvent]) -> Vec<Reported> {
+        touchscreen.read_events(events.iter().copied()).unwrap();
+        touchscreen.touches().into_iter().map(|touch| (touch.id, touch.phase, touch.points)).collect()
     }
 
-    // TODO: fn click(&self) -> Option(usize, usize) : returns Some(pt) if the user clicked there.
-    // TODO: Update `trail` to return a reference to the VecDeque. Returning an owned Vec is definitely slow :D
-    pub(crate) fn trail(&mut self) -> Vec<(usize, usize)> {
-        let res = self.trail.iter().cloned().collect();
-        self.trail.truncate(0);
-        res
+    /// Touches the screen with one finger, the way a single-touch screen reports it.
+    fn tap(x: i32, y: i32) -> Vec<InputEvent> {
+        vec![
+            event(EV_KEY, Key::BTN_TOUCH.0, 1), abs(Abs::X, x), abs(Abs::Y, y), report(),
+            event(EV_KEY, Key::BTN_TOUCH.0, 0), report(),
+        ]
     }
 
-    /// Returns whether or not the user has lifted their finger. If `poll` returns true on a this
-    /// device you can safely assume that touches have begun.
-    pub(crate) fn touches_ended(&self) -> bool {
-        self.touches_ended
+    #[test]
+    fn single_touch_presses_get_new_ids() {
+        let mut touchscreen = Touchscreen::default();
+        assert_eq!(read(&mut touchscreen, &tap(1, 2)), [(0, TouchPhase::Ended, vec![(1, 2)])]);
+        assert_eq!(read(&mut touchscreen, &tap(3, 4)), [(1, TouchPhase::Ended, vec![(3, 4)])]);
+    }
+
+    #[test]
+    fn quick_taps_are_all_reported() {
+        let mut touchscreen = Touchscreen::default();
+        let events = [tap(1, 2), tap(3, 4)].concat();
+        assert_eq!(read(&mut touchscreen, &events), [
+            (0, TouchPhase::Ended, vec![(1, 2)]),
+            (1, TouchPhase::Ended, vec![(3, 4)]),
+        ]);
+    }
+
+    #[test]
+    fn reused_tracking_ids_get_new_ids() {
+        let mut touchscreen = Touchscreen::default();
+        let events = [
+            abs(Abs::MT_SLOT, 0), abs(Abs::MT_TRACKING_ID, 7), abs(Abs::MT_POSITION_X, 1), abs(Abs::MT_POSITION_Y, 2), report(),
+            abs(Abs::MT_TRACKING_ID, -1), report(),
+            abs(Abs::MT_TRACKING_ID, 7), abs(Abs::MT_POSITION_X, 3), report(),
+        ];
+        assert_eq!(read(&mut touchscreen, &events), [
+            (0, TouchPhase::Ended, vec![(1, 2)]),
+            (1, TouchPhase::Began, vec![(3, 2)]),
+        ]);
+        assert_eq!(read(&mut touchscreen, &[abs(Abs::MT_POSITION_Y, 4), report()]), [(1, TouchPhase::Moved, vec![(3, 4)])]);
+    }
+
+    #[test]
+    fn dropped_events_end_every_contact() {
+        let mut touchscreen = Touchscreen::default();
+        let events = [
+            abs(Abs::MT_SLOT, 0), abs(Abs::MT_TRACKING_ID, 10), abs(Abs::MT_POSITION_X, 1), abs(Abs::MT_POSITION_Y, 1),
+            abs(Abs::MT_SLOT, 1), abs(Abs::MT_TRACKING_ID, 11), abs(Abs::MT_POSITION_X, 5), abs(Abs::MT_POSITION_Y, 5), report(),
+        ];
+        read(&mut touchscreen, &events);
+
+        // Slot 0's lift was lost. Slot 1 is still down and keeps moving, without a new tracking id.
+        let events = [
+            event(EV_SYN, Syn::DROPPED.0, 0), abs(Abs::MT_POSITION_X, 9), report(),
+            abs(Abs::MT_SLOT, 1), abs(Abs::MT_POSITION_X, 6), report(),
+        ];
+        assert_eq!(read(&mut touchscreen, &events), [
+            (0, TouchPhase::Ended, vec![]),
+            (1, TouchPhase::Ended, vec![]),
+            (2, TouchPhase::Began, vec![(6, 5)]),
+        ]);
+
+        // The lift that comes after ends the new contact, and slot 0 stays empty.
+        let events = [abs(Abs::MT_TRACKING_ID, -1), report()];
+        assert_eq!(read(&mut touchscreen, &events), [(2, TouchPhase::Ended, vec![])]);
+        assert_eq!(read(&mut touchscreen, &[report()]), []);
+    }
+
+    #[test]
+    fn single_touch_recovers_from_dropped_events() {
+        let mut touchscreen = Touchscreen::default();
+        read(&mut touchscreen, &[event(EV_KEY, Key::BTN_TOUCH.0, 1), abs(Abs::X, 1), abs(Abs::Y, 2), report()]);
+
+        // The lift was lost, and the next tap is seen whole.
+        let events = [event(EV_SYN, Syn::DROPPED.0, 0), report()].into_iter().chain(tap(3, 4)).collect::<Vec<_>>();
+        assert_eq!(read(&mut touchscreen, &events), [
+            (0, TouchPhase::Ended, vec![]),
+            (1, TouchPhase::Ended, vec![(3, 4)]),
+        ]);
+        assert_eq!(read(&mut touchscreen, &[report()]), []);
+    }
+
+    #[test]
+    fn fingers_in_other_slots_keep_their_ids() {
+        let mut touchscreen = Touchscreen::default();
+        let events = [
+            abs(Abs::MT_SLOT, 0), abs(Abs::MT_TRACKING_ID, 10), abs(Abs::MT_POSITION_X, 1), abs(Abs::MT_POSITION_Y, 1),
+            abs(Abs::MT_SLOT, 1), abs(Abs::MT_TRACKING_ID, 11), abs(Abs::MT_POSITION_X, 5), abs(Abs::MT_POSITION_Y, 5), report(),
+        ];
+        assert_eq!(read(&mut touchscreen, &events), [
+            (0, TouchPhase::Began, vec![(1, 1)]),
+            (1, TouchPhase::Began, vec![(5, 5)]),
+        ]);
+
+        let events = [abs(Abs::MT_SLOT, 0), abs(Abs::MT_TRACKING_ID, -1), abs(Abs::MT_SLOT, 1), abs(Abs::MT_POSITION_X, 6), report()];
+        assert_eq!(read(&mut touchscreen, &events), [
+            (0, TouchPhase::Ended, vec![]),
+            (1, TouchPhase::Moved, vec![(6, 5)]),
+        ]);
+        assert_eq!(read(&mut touchscreen, &[report()]), [(1, TouchPhase::Moved, vec![])]);
     }
 }
@@ -0,0 +1,13 @@
+//! A toolkit for touch-first apps on the Raspberry Pi 7-inch touchscreen: drawing to the
+//! framebuffer, rendering text, and reading input devices. `main.rs` is a demo app built on it.
+pub mod gfx;
+pub mod input;
+
+use crate::gfx::screen::Screen;
+
+// Define some constants for the operation environment.
+
+pub const SCREEN_W: usize = 800;
+pub const SCREEN_H: usize = 480;
+pub const BUFFER_SIZE: usize = SCREEN_W * SCREEN_H * 3;
+pub const EVENT_BUFFER_LEN: usize = 16;
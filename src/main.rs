@@ -1,87 +1,22 @@
-mod gfx;
-mod input;
-
-use crate::gfx::color::NamedColor::{Black, Yellow};
-use crate::gfx::color::{Colorful, ColorfulCycle, NamedColor, RGBA};
-use crate::gfx::screen::Screen;
-use gfx::text::TextRenderer;
+use rpi_thing::gfx::color::cycle::{IntoCycle, Rainbow};
+use rpi_thing::gfx::screen::Screen;
+use rpi_thing::gfx::theme::Themes;
+#[cfg(not(feature = "truetype"))]
+use rpi_thing::gfx::text::bitmap::{BitmapFont, BitmapTextRenderer};
+#[cfg(feature = "truetype")]
+use rpi_thing::gfx::text::antialias::Rendering;
+#[cfg(feature = "truetype")]
+use rpi_thing::gfx::text::truetype::TextRenderer;
 use image::{load_from_memory, ImageReader};
-use itertools::Itertools;
-use std::cell::RefCell;
+use std::collections::HashMap;
 use std::error::Error;
-use std::fs::File;
-use std::io::{Read, Write};
-use std::ops::{Deref, DerefMut};
 use std::path::PathBuf;
 use std::thread::sleep;
 use std::time::Duration;
-use NamedColor::White;
-use crate::input::device::InputDevice;
-use crate::input::touchscreen::Touchscreen;
-// Define some constants for the operation environment.
-
-const SCREEN_W: usize = 800;
-const SCREEN_H: usize = 480;
-const BUFFER_SIZE: usize = SCREEN_W * SCREEN_H * 3;
-const EVENT_SIZE: usize = 16;
-const EVENT_BUFFER_LEN: usize = 16;
-const EV_SYN: u8 = 0;
-const EV_KEY: u8 = 3;
-const ABSOLUTE_X_POS: u8 = 0;
-const ABSOLUTE_Y_POS: u8 = 1;
-const TOUCHES_BEGAN: u8 = 53;
-const TOUCHES_ENDED: u8 = 57;
-
-/// The RainbowCycle gradually changes through every maximum brightness color that can be
-/// represented. It acts like color changing yarn, where you can't necessarily predict which pixels
-/// will be which color, but could work nicely when randomly distributed.
-struct RainbowCycleBuilder {
-    hue: u8,
-}
-
-impl RainbowCycleBuilder {
-    fn new() -> Self {
-        Self { hue: 0 }
-    }
-
-    fn into_cycle(self) -> ColorfulCycle {
-        ColorfulCycle { generator: RefCell::new(Box::new(self)) }
-    }
-
-    /// Converts HSL value from hue to RGBA.
-    /// Source: https://github.com/judge2005/arduinoHSV/blob/master/arduinoHSV.c
-    // I tried to figure it out but my brain is fried.
-    fn as_rgba(&self) -> RGBA {
-        let h = ((self.hue as u16 * 192) / 256) as u8;
-        let i = h / 32;
-        let f = (h % 32) * 8;
-
-        let s_inv = 0;
-        let f_inv = 255 - f;
-
-        let pv = (128 * s_inv as u16 / 256) as u8;
-        let qv = (128 * (256 - 255 * f as u16 / 256) / 256) as u8;
-        let tv = (128 * (256 - 255 * f_inv as u16 / 256) / 256) as u8;
-
-        match i {
-            0 => (u8::MAX, tv, pv, u8::MAX),
-            1 => (qv, u8::MAX, pv, u8::MAX),
-            2 => (pv, u8::MAX, tv, u8::MAX),
-            3 => (pv, qv, u8::MAX, u8::MAX),
-            4 => (tv, pv, u8::MAX, u8::MAX),
-            _ => (u8::MAX, pv, qv, u8::MAX),
-        }
-    }
-}
-
-impl Iterator for RainbowCycleBuilder {
-    type Item = RGBA;
-
-    fn next(&mut self) -> Option<Self::Item> {
-        self.hue = self.hue.wrapping_add(1);
-        Some(self.as_rgba())
-    }
-}
+use rpi_thing::input::device::InputDevice;
+use rpi_thing::input::discovery;
+use rpi_thing::input::touchscreen::{TouchPhase, Touchscreen};
+use rpi_thing::SCREEN_W;
 
 /// Loads an image from provided image data (such as from `include_bytes!()`). This uses the image
 /// crates "guess format" method so if it doesn't work for your input, just modify this function.
@@ -92,6 +27,8 @@ fn decode_image(data: &[u8]) -> Result<Vec<u8>, Box<dyn Error>> {
 }
 
 /// Loads an image from disk into memory.
+// The demo only uses images built into the binary, but apps copying it often want this too.
+#[allow(dead_code)]
 fn load_image(path: PathBuf) -> Result<Vec<u8>, Box<dyn Error>> {
     let image = ImageReader::open(path)?.decode()?;
     let rgba = image.as_rgba8().unwrap();
@@ -105,18 +42,9 @@ fn hide_cursor() {
     println!("\x1b[?25l");
 }
 
-/// Finds the touchscreen hidden amongst all input devices. Returns a path to it.
-fn find_touchscreen() -> Option<PathBuf> {
-    // TODO
-    // reading from /proc/bus/input/devices *could* work.
-    // H: Handlers=sysrq kbd leds event0
-    Some(PathBuf::from("/dev/input/event3"))
-}
-
 // TODO: Touchscreen if - delta
 //       Touchscreen if - click pos
 //       Gfx - Render text
-//       Gfx - blend alpha channel
 // Stretch goals:
 // - Detect screen resolution - requires ioctl
 // - Set pixel depth to 24 bits - requires ioctl
@@ -126,63 +54,76 @@ fn main() {
     // If any cursor is blinking, turn that off.
     hide_cursor();
     // Locate the touchscreen device
-    let touchscreen_handle = find_touchscreen().unwrap();
+    let touchscreen_handle = discovery::find_touchscreen().expect("No touchscreen found");
     // Open the touchscreen device.
-    let mut touchscreen: InputDevice<Touchscreen> = InputDevice::new(File::open(touchscreen_handle).unwrap());
+    let mut touchscreen: InputDevice<Touchscreen> = touchscreen_handle.open().unwrap();
     // Open the screen device. Unsafe because we need unrestricted write to a region of memory.
     let mut screen = unsafe { Screen::new("/dev/fb0".parse().unwrap()).unwrap() };
 
-    // Set up rainbow color generator
-    let rainbow = RainbowCycleBuilder::new().into_cycle();
-
-    // Create a text renderer. You need to leak it to ensure that the bytes behind the fonts never
-    // deallocate. Trust me, this actually is easier.
-    let mut text_renderer = Box::leak(Box::new(TextRenderer::default()));
+    // Pick the look of the app. Themes can also be loaded from a file with `Theme::load`.
+    let themes = Themes::default();
+    let theme = themes.current();
 
-    // Load font - OpenSans Condensed Light can display *a lot* of text on the pi touchscreen.
-    text_renderer.load_font("OpenSans-CondLight", include_bytes!("OpenSans-CondLight.ttf"));
+    // Set up rainbow color generator
+    let rainbow = Rainbow::new().into_cycle();
+
+    // Create a text renderer. Fonts can also be loaded from disk with `load_font_file`.
+    #[cfg(feature = "truetype")]
+    let text_renderer = {
+        let mut text_renderer = TextRenderer::default();
+        // Load font - OpenSans Condensed Light can display *a lot* of text on the pi touchscreen.
+        text_renderer.load_font("OpenSans-CondLight", &include_bytes!("OpenSans-CondLight.ttf")[..]).unwrap();
+        // Light strokes look faint once blended, so make them a little heavier.
+        text_renderer.set_rendering(Rendering { gamma: 1.4, stem_darkening: 0.3, ..Default::default() });
+        text_renderer
+    };
+
+    // Without TrueType support, the built-in bitmap font stands in for the theme's font.
+    #[cfg(not(feature = "truetype"))]
+    let text_renderer = {
+        let mut text_renderer = BitmapTextRenderer::default();
+        text_renderer.load_font(&theme.body_font.family, BitmapFont::builtin());
+        text_renderer
+    };
 
     // Render some text into RGBA
-    let hello_text = text_renderer.render("This is my Raspberry Pi Touchscreen project", "OpenSans-CondLight", 18.0, &rainbow);
+    let font = &theme.body_font;
+    let hello_text = text_renderer.render("This is my Raspberry Pi Touchscreen project", &font.family, font.size, &rainbow);
 
     // Load a bundled image asset
     let close_icon = &decode_image(include_bytes!("close.png")).unwrap();
-    let sushi = screen.render_image(&decode_image(include_bytes!("sushi.png")).unwrap(), &Black);
-    // Load the sample text
-    let text = hello_text.into_blittable(&screen, &Yellow);
-
-    screen.fill(&Black);
+    let sushi = screen.render_image(&decode_image(include_bytes!("sushi.png")).unwrap(), &theme.background);
+    screen.fill(&theme.background);
     screen.blit_image(0, 0, 750, &sushi);
-    screen.blend_image(0, 0, 50, &close_icon);
-    screen.draw_rect(SCREEN_W - text.width - 30 - 2, 18, text.width + 2, 18 + 4, 4, &Yellow, &Yellow);
-    screen.blit_image(SCREEN_W - text.width - 30, 20, text.width, &text.data);
+    screen.blend_image(0, 0, 50, close_icon);
+    screen.draw_text(SCREEN_W - hello_text.width - 30, 20, &hello_text, &rainbow);
 
     // Loop through values for corner radius
     for i in 0..9 {
-        screen.draw_rect(75 + i * 50, 10, 30, 30, i, &[255, 255, 255, 255 / (i as u8 + 1)], &White);
+        screen.draw_rect(75 + i * 50, 10, 30, 30, i, &[255, 255, 255, 255 / (i as u8 + 1)], &theme.text);
     }
 
-    let mut last_pos: Option<(usize, usize)> = None;
+    // Where each finger was last drawn, by touch id, so several can draw at once.
+    let mut last_pos: HashMap<i32, (usize, usize)> = HashMap::new();
     let mut run = true;
     while run {
         if touchscreen.poll().unwrap() {
-            for point in touchscreen.trail().into_iter().rev() {
-                if let Some(last_pos) = last_pos {
-                    screen.draw_line(point.0, point.1, last_pos.0, last_pos.1, &rainbow);
-                    // screen.draw_line(last_pos.0, last_pos.1 + 1, point.0, point.1 - 1, &rainbow);
+            for touch in touchscreen.touches() {
+                for &point in &touch.points {
+                    if let Some(last_pos) = last_pos.insert(touch.id, point) {
+                        screen.draw_line(point.0, point.1, last_pos.0, last_pos.1, &rainbow);
+                    }
+
+                    // Detect corner kill
+                    if point.0 < 50 && point.1 < 50 {
+                        run = false;
+                    }
                 }
 
-                last_pos = Some(point);
-
-                // Detect corner kill
-                if point.0 < 50 && point.1 < 50 {
-                    run = false;
+                if touch.phase == TouchPhase::Ended {
+                    last_pos.remove(&touch.id);
                 }
             }
-
-            if touchscreen.touches_ended() {
-                last_pos = None;
-            }
         }
 
         sleep(Duration::from_millis(16));